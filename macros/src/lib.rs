@@ -41,6 +41,28 @@ use std::num::NonZeroUsize;
 /// }
 /// ```
 ///
+/// The return type of the test function is forwarded, so tests can return a
+/// `Result`. The checks run before the result is returned, and panic with a
+/// report of every violation found, which also works with `#[should_panic]`:
+///
+/// ```rust
+/// #[global_allocator]
+/// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+///
+/// #[checkers::test]
+/// fn test_parse() -> Result<(), std::num::ParseIntError> {
+///     let n = String::from("42").parse::<u32>()?;
+///     assert_eq!(42, n);
+///     Ok(())
+/// }
+///
+/// #[checkers::test]
+/// #[should_panic(expected = "allocation checks failed")]
+/// fn test_leak() {
+///     std::mem::forget(Box::new(42));
+/// }
+/// ```
+///
 /// Using a custom verifier:
 ///
 /// ```rust
@@ -179,11 +201,12 @@ pub fn test(args: TokenStream, item: TokenStream) -> TokenStream {
                     s.reserve(#capacity);
                 }
 
-                checkers::with_unmuted(|| #body);
+                let output = checkers::with_unmuted(|| #body);
 
                 let state = &mut *s.borrow_mut();
                 #verify
-            });
+                output
+            })
         }
     };
 
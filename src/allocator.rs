@@ -1,5 +1,9 @@
-use crate::{AllocZeroed, Event, Realloc, Region};
-use std::alloc::{GlobalAlloc, Layout, System};
+use crate::{AllocZeroed, BudgetExceeded, Event, Realloc, Region, State};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    ptr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 /// Allocator that needs to be installed.
 ///
@@ -16,6 +20,21 @@ use std::alloc::{GlobalAlloc, Layout, System};
 /// ```
 pub struct Allocator<T = System> {
     delegate: T,
+    /// The maximum number of bytes permitted to be live at once.
+    max_bytes: Option<usize>,
+    /// The maximum number of frames to capture for each allocation. Zero
+    /// disables capturing.
+    #[cfg(feature = "backtrace")]
+    backtrace_depth: usize,
+    /// Allocations smaller than this are not recorded.
+    min_tracked_size: usize,
+    /// Hook invoked for every recorded event.
+    event_hook: Option<fn(&Event)>,
+    /// If recorded events are emitted through `tracing`.
+    #[cfg(feature = "tracing")]
+    tracing: bool,
+    /// The number of allocations remaining before they fail, if limited.
+    oom_remaining: Option<AtomicUsize>,
 }
 
 impl<T> Allocator<T> {
@@ -28,7 +47,552 @@ impl<T> Allocator<T> {
     /// static ALLOCATOR: checkers::Allocator = checkers::Allocator::new(std::alloc::System);
     /// ```
     pub const fn new(delegate: T) -> Allocator<T> {
-        Allocator { delegate }
+        Allocator {
+            delegate,
+            max_bytes: None,
+            #[cfg(feature = "backtrace")]
+            backtrace_depth: 0,
+            min_tracked_size: 0,
+            event_hook: None,
+            #[cfg(feature = "tracing")]
+            tracing: false,
+            oom_remaining: None,
+        }
+    }
+
+    /// Construct a builder for an allocator with a custom delegate global
+    /// allocator.
+    ///
+    /// See [AllocatorBuilder] for the available options.
+    pub const fn builder(delegate: T) -> AllocatorBuilder<T> {
+        AllocatorBuilder {
+            allocator: Self::new(delegate),
+        }
+    }
+
+    /// Refuse allocations which would cause more than `max_bytes` to be live
+    /// at once, including growth through reallocations.
+    ///
+    /// Refused allocations return null and are recorded as
+    /// [Event::BudgetExceeded]. Only allocations performed while unmuted
+    /// count towards the budget.
+    ///
+    /// This requires the delegate allocator to be `Copy`, so that the
+    /// allocator can be configured in a `static` item.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #[global_allocator]
+    /// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system().with_max_bytes(1024);
+    ///
+    /// let snapshot = checkers::with(|| {
+    ///     let mut a = Vec::<u8>::new();
+    ///     assert!(a.try_reserve_exact(1000).is_ok());
+    ///
+    ///     let mut b = Vec::<u8>::new();
+    ///     assert!(b.try_reserve_exact(100).is_err());
+    /// });
+    ///
+    /// assert_eq!(Some(1024), snapshot.max_bytes);
+    /// assert_eq!(1000, snapshot.peak_memory);
+    ///
+    /// let mut violations = Vec::new();
+    /// snapshot.validate(&mut violations);
+    /// assert_eq!(1, violations.len());
+    /// ```
+    pub const fn with_max_bytes(self, max_bytes: usize) -> Allocator<T>
+    where
+        T: Copy,
+    {
+        Allocator {
+            max_bytes: Some(max_bytes),
+            ..self
+        }
+    }
+
+    /// Access the configured memory budget, if any.
+    ///
+    /// See [Allocator::with_max_bytes].
+    pub fn max_bytes(&self) -> Option<usize> {
+        self.max_bytes
+    }
+
+    /// Access the delegate allocator.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Allocator, FailingAllocator};
+    /// use std::alloc::System;
+    ///
+    /// #[global_allocator]
+    /// static ALLOCATOR: Allocator<FailingAllocator> = Allocator::new(FailingAllocator::new(System, 0));
+    ///
+    /// ALLOCATOR.delegate().reset(10);
+    /// ```
+    pub fn delegate(&self) -> &T {
+        &self.delegate
+    }
+
+    /// Capture a backtrace of at most `depth` frames for every allocation,
+    /// which can later be looked up through [Snapshot::alloc_backtrace].
+    ///
+    /// Captured frames include the frames of the allocator itself. Capturing
+    /// is expensive, so prefer a shallow depth if many allocations are
+    /// performed.
+    ///
+    /// This requires the delegate allocator to be `Copy`, so that the
+    /// allocator can be configured in a `static` item.
+    ///
+    /// [Snapshot::alloc_backtrace]: crate::Snapshot::alloc_backtrace
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #[global_allocator]
+    /// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system().with_backtraces(32);
+    ///
+    /// let snapshot = checkers::with(|| {
+    ///     let _ = Box::into_raw(Box::new(42));
+    /// });
+    ///
+    /// let mut violations = Vec::new();
+    /// snapshot.validate(&mut violations);
+    /// assert_eq!(1, violations.len());
+    ///
+    /// let region = match violations[0] {
+    ///     checkers::Violation::Leaked { region } => region,
+    ///     _ => unreachable!(),
+    /// };
+    ///
+    /// assert!(snapshot.alloc_backtrace(region).is_some());
+    /// ```
+    #[cfg(feature = "backtrace")]
+    pub const fn with_backtraces(self, depth: usize) -> Allocator<T>
+    where
+        T: Copy,
+    {
+        Allocator {
+            backtrace_depth: depth,
+            ..self
+        }
+    }
+
+    /// Pass allocations smaller than `min_tracked_size` bytes straight through
+    /// to the delegate allocator without recording them.
+    ///
+    /// This reduces the noise of many small short-lived allocations when
+    /// profiling. Deallocations of small regions are not recorded either, and
+    /// reallocations are only skipped if both the old and the new size are
+    /// small. The number of skipped allocations and reallocations is available
+    /// through [Snapshot::skipped_allocs].
+    ///
+    /// **Note:** this is a profiling-only mode. Since not every allocation is
+    /// recorded, validating the recorded events might report spurious
+    /// violations, like when a small region is reallocated to a large one.
+    ///
+    /// This requires the delegate allocator to be `Copy`, so that the
+    /// allocator can be configured in a `static` item.
+    ///
+    /// [Snapshot::skipped_allocs]: crate::Snapshot::skipped_allocs
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #[global_allocator]
+    /// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system().with_min_tracked_size(64);
+    ///
+    /// let snapshot = checkers::with(|| {
+    ///     let _ = Box::new([0u8; 16]);
+    ///     let _ = Box::new([0u8; 128]);
+    /// });
+    ///
+    /// assert_eq!(1, snapshot.events.allocs());
+    /// assert_eq!(1, snapshot.events.frees());
+    /// assert_eq!(1, snapshot.skipped_allocs);
+    /// assert_eq!(128, snapshot.peak_memory);
+    /// ```
+    pub const fn with_min_tracked_size(self, min_tracked_size: usize) -> Allocator<T>
+    where
+        T: Copy,
+    {
+        Allocator {
+            min_tracked_size,
+            ..self
+        }
+    }
+
+    /// Access the minimum size of recorded allocations.
+    ///
+    /// See [Allocator::with_min_tracked_size].
+    pub fn min_tracked_size(&self) -> usize {
+        self.min_tracked_size
+    }
+
+    /// Invoke `hook` with every event as it is recorded by the allocator.
+    ///
+    /// This can be used to stream events somewhere else as they happen, like
+    /// to a log, rather than inspecting them after the fact through a
+    /// [Snapshot].
+    ///
+    /// **Warning:** the hook is called from inside the allocator, so it is
+    /// subject to all the restrictions of [GlobalAlloc]:
+    /// * The hook must not panic, since unwinding out of an allocator is
+    ///   undefined behavior.
+    /// * The hook runs while the allocator is muted, so any allocations it
+    ///   performs are not recorded and do not invoke the hook again. They
+    ///   still pass through the delegate allocator, so prefer hooks which do
+    ///   not allocate at all, like ones that update atomic counters.
+    ///
+    /// This requires the delegate allocator to be `Copy`, so that the
+    /// allocator can be configured in a `static` item.
+    ///
+    /// [Snapshot]: crate::Snapshot
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// fn hook(event: &checkers::Event) {
+    ///     if let checkers::Event::Alloc(..) = event {
+    ///         ALLOCS.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// #[global_allocator]
+    /// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system().with_event_hook(hook);
+    ///
+    /// let snapshot = checkers::with(|| {
+    ///     let _ = Box::new(42);
+    /// });
+    ///
+    /// assert_eq!(1, snapshot.events.allocs());
+    /// assert_eq!(1, ALLOCS.load(Ordering::SeqCst));
+    /// ```
+    pub const fn with_event_hook(self, hook: fn(&Event)) -> Allocator<T>
+    where
+        T: Copy,
+    {
+        Allocator {
+            event_hook: Some(hook),
+            ..self
+        }
+    }
+
+    /// Emit every allocation, reallocation and free recorded by the allocator
+    /// as a [tracing] event with the target `checkers::alloc`.
+    ///
+    /// Events are emitted at the `TRACE` level, with the `kind` of the event
+    /// and the `ptr`, `size` and `align` of the affected region as fields.
+    /// Reallocations also include the `free_ptr` and `free_size` of the region
+    /// they freed.
+    ///
+    /// Subscribers are called while the allocator is muted, so allocations
+    /// they perform are not recorded and do not emit events of their own.
+    ///
+    /// This requires the delegate allocator to be `Copy`, so that the
+    /// allocator can be configured in a `static` item.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #[global_allocator]
+    /// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system().with_tracing(true);
+    ///
+    /// let snapshot = checkers::with(|| {
+    ///     let _ = Box::new(42);
+    /// });
+    ///
+    /// assert_eq!(1, snapshot.events.allocs());
+    /// ```
+    #[cfg(feature = "tracing")]
+    pub const fn with_tracing(self, tracing: bool) -> Allocator<T>
+    where
+        T: Copy,
+    {
+        Allocator { tracing, ..self }
+    }
+
+    /// Test if the next allocation should fail since the configured number of
+    /// allocations has been exhausted, counting it as performed if not.
+    fn is_out_of_memory(&self) -> bool {
+        match &self.oom_remaining {
+            Some(remaining) => remaining
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_err(),
+            None => false,
+        }
+    }
+
+    /// Invoke the event hook, if any, for an event which has been recorded.
+    fn notify(&self, event: Event) {
+        #[cfg(feature = "tracing")]
+        {
+            if self.tracing {
+                let _g = crate::mute_guard(true);
+                trace_event(event);
+            }
+        }
+
+        if let Some(hook) = self.event_hook {
+            let _g = crate::mute_guard(true);
+            hook(&event);
+        }
+    }
+
+    /// Capture a backtrace for the most recently recorded event.
+    #[cfg(feature = "backtrace")]
+    fn capture_backtrace(&self, state: &mut State) {
+        if self.backtrace_depth == 0 {
+            return;
+        }
+
+        // Note: capturing allocates, which we don't want to be tracked.
+        let _g = crate::mute_guard(true);
+        let mut frames = Vec::new();
+
+        backtrace::trace(|frame| {
+            frames.push(backtrace::BacktraceFrame::from(frame.clone()));
+            frames.len() < self.backtrace_depth
+        });
+
+        let index = state.events.len().saturating_sub(1);
+        state.backtraces.insert(index, frames.into());
+    }
+}
+
+impl<T> Allocator<T> {
+    /// Test if an allocation of `size` bytes is large enough to be recorded.
+    /// Counts it as skipped if it isn't.
+    fn is_tracked(&self, size: usize) -> bool {
+        if size >= self.min_tracked_size {
+            return true;
+        }
+
+        crate::with_recording_state(|s| {
+            s.skipped_allocs += 1;
+        });
+
+        false
+    }
+
+    /// Test if an allocation of `size` bytes, which frees `freed` bytes, is
+    /// permitted by the memory budget. Records [Event::BudgetExceeded] if it
+    /// isn't.
+    fn check_budget(&self, size: usize, freed: usize) -> bool {
+        let max_bytes = match self.max_bytes {
+            Some(max_bytes) => max_bytes,
+            None => return true,
+        };
+
+        let exceeded = crate::with_recording_state(|s| {
+            s.max_bytes = Some(max_bytes);
+
+            if s.memory_used.saturating_sub(freed).saturating_add(size) <= max_bytes {
+                return None;
+            }
+
+            let event = Event::BudgetExceeded(BudgetExceeded {
+                requested: size,
+                memory_used: s.memory_used,
+                max_bytes,
+            });
+
+            s.events.push(event);
+            Some(event)
+        });
+
+        match exceeded {
+            Some(event) => {
+                self.notify(event);
+                false
+            }
+            None => true,
+        }
+    }
+}
+
+/// Emit a recorded event through `tracing`.
+#[cfg(feature = "tracing")]
+fn trace_event(event: Event) {
+    macro_rules! trace {
+        ($kind:expr, $region:expr $(, $($rest:tt)*)?) => {
+            tracing::trace!(
+                target: "checkers::alloc",
+                kind = $kind,
+                ptr = %format_args!("{:#x}", $region.ptr),
+                size = $region.size,
+                align = $region.align,
+                $($($rest)*)?
+            )
+        };
+    }
+
+    match event {
+        Event::Alloc(region) => trace!("alloc", region),
+        Event::AllocZeroed(AllocZeroed { alloc, .. }) => trace!("alloc_zeroed", alloc),
+        Event::Free(region) => trace!("free", region),
+        Event::Realloc(Realloc { free, alloc, .. }) => trace!(
+            "realloc",
+            alloc,
+            free_ptr = %format_args!("{:#x}", free.ptr),
+            free_size = free.size,
+        ),
+        _ => (),
+    }
+}
+
+/// Update the amount of memory live in the given state.
+fn track_memory(state: &mut State, freed: usize, allocated: usize) {
+    state.memory_used = state
+        .memory_used
+        .saturating_sub(freed)
+        .saturating_add(allocated);
+    state.peak_memory = usize::max(state.memory_used, state.peak_memory);
+}
+
+/// Builder for configuring an [Allocator], constructed through
+/// [Allocator::builder].
+///
+/// Building without configuring any options produces the same allocator as
+/// [Allocator::new]. Like the `with_*` methods of [Allocator], configuring
+/// requires the delegate allocator to be `Copy`, so that the allocator can be
+/// built in a `static` item.
+///
+/// # Examples
+///
+/// ```rust
+/// use checkers::Allocator;
+/// use std::alloc::System;
+///
+/// #[global_allocator]
+/// static ALLOCATOR: Allocator = Allocator::builder(System)
+///     .min_tracked_size(16)
+///     .max_bytes(1 << 20)
+///     .build();
+///
+/// assert_eq!(16, ALLOCATOR.min_tracked_size());
+/// assert_eq!(Some(1 << 20), ALLOCATOR.max_bytes());
+/// ```
+pub struct AllocatorBuilder<T = System> {
+    allocator: Allocator<T>,
+}
+
+impl<T> AllocatorBuilder<T> {
+    /// Refuse allocations which would exceed `max_bytes`.
+    ///
+    /// See [Allocator::with_max_bytes].
+    pub const fn max_bytes(self, max_bytes: usize) -> Self
+    where
+        T: Copy,
+    {
+        Self {
+            allocator: self.allocator.with_max_bytes(max_bytes),
+        }
+    }
+
+    /// Only record allocations of at least `min_tracked_size` bytes.
+    ///
+    /// See [Allocator::with_min_tracked_size].
+    pub const fn min_tracked_size(self, min_tracked_size: usize) -> Self
+    where
+        T: Copy,
+    {
+        Self {
+            allocator: self.allocator.with_min_tracked_size(min_tracked_size),
+        }
+    }
+
+    /// Capture backtraces of allocations with at most `depth` frames.
+    ///
+    /// See [Allocator::with_backtraces].
+    #[cfg(feature = "backtrace")]
+    pub const fn capture_backtraces(self, depth: usize) -> Self
+    where
+        T: Copy,
+    {
+        Self {
+            allocator: self.allocator.with_backtraces(depth),
+        }
+    }
+
+    /// Invoke `hook` for every recorded event.
+    ///
+    /// See [Allocator::with_event_hook].
+    pub const fn event_hook(self, hook: fn(&Event)) -> Self
+    where
+        T: Copy,
+    {
+        Self {
+            allocator: self.allocator.with_event_hook(hook),
+        }
+    }
+
+    /// Emit recorded events through `tracing`.
+    ///
+    /// See [Allocator::with_tracing].
+    #[cfg(feature = "tracing")]
+    pub const fn tracing(self, tracing: bool) -> Self
+    where
+        T: Copy,
+    {
+        Self {
+            allocator: self.allocator.with_tracing(tracing),
+        }
+    }
+
+    /// Permit `n` successful allocations, after which every allocation and
+    /// reallocation fails.
+    ///
+    /// This behaves like using a [FailingAllocator] as the delegate. Failures
+    /// are recorded as [Event::AllocFailed], [Event::AllocZeroedFailed] or
+    /// [Event::ReallocFailed]. Only allocations performed while unmuted are
+    /// counted and failed.
+    ///
+    /// [FailingAllocator]: crate::FailingAllocator
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::Allocator;
+    /// use std::alloc::System;
+    ///
+    /// #[global_allocator]
+    /// static ALLOCATOR: Allocator = Allocator::builder(System).oom_after(1).build();
+    ///
+    /// let snapshot = checkers::with(|| {
+    ///     let mut a = Vec::<u8>::new();
+    ///     assert!(a.try_reserve_exact(10).is_ok());
+    ///
+    ///     let mut b = Vec::<u8>::new();
+    ///     assert!(b.try_reserve_exact(10).is_err());
+    /// });
+    ///
+    /// assert_eq!(3, snapshot.events.len());
+    /// assert!(snapshot.events[1].is_failed());
+    /// ```
+    pub const fn oom_after(self, n: usize) -> Self
+    where
+        T: Copy,
+    {
+        Self {
+            allocator: Allocator {
+                oom_remaining: Some(AtomicUsize::new(n)),
+                ..self.allocator
+            },
+        }
+    }
+
+    /// Build the configured allocator.
+    pub const fn build(self) -> Allocator<T>
+    where
+        T: Copy,
+    {
+        self.allocator
     }
 }
 
@@ -51,79 +615,115 @@ where
     T: GlobalAlloc,
 {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        let ptr = self.delegate.alloc(layout);
+        if !crate::is_muted() && !self.is_tracked(layout.size()) {
+            return self.delegate.alloc(layout);
+        }
+
+        if !crate::is_muted() && !self.check_budget(layout.size(), 0) {
+            return ptr::null_mut();
+        }
+
+        let ptr = if !crate::is_muted() && self.is_out_of_memory() {
+            ptr::null_mut()
+        } else {
+            self.delegate.alloc(layout)
+        };
 
         // Note: On null return early, caller is likely to panic or handle OOM
         // scenario gracefully.
         // TODO: Consider emitting diagnostics.
         if crate::is_muted() || ptr.is_null() {
             if ptr.is_null() {
-                crate::with_state(move |s| {
-                    s.borrow_mut().events.push(Event::AllocFailed);
+                crate::with_recording_state(|s| {
+                    s.events.push(Event::AllocFailed);
                 });
+                self.notify(Event::AllocFailed);
             }
 
             return ptr;
         }
 
-        crate::with_state(move |s| {
-            s.borrow_mut().events.push(Event::Alloc(Region {
-                ptr: ptr.into(),
-                size: layout.size(),
-                align: layout.align(),
-            }));
+        let event = Event::Alloc(Region::from_layout(ptr.into(), layout));
+
+        crate::with_recording_state(move |s| {
+            s.events.push(event);
+            track_memory(s, 0, layout.size());
+
+            #[cfg(feature = "backtrace")]
+            self.capture_backtrace(s);
         });
 
+        self.notify(event);
         ptr
     }
 
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
-        self.delegate.dealloc(ptr, layout);
-
-        if crate::is_muted() {
+        if crate::is_muted() || layout.size() < self.min_tracked_size {
+            self.delegate.dealloc(ptr, layout);
             return;
         }
 
-        crate::with_state(move |s| {
-            s.borrow_mut().events.push(Event::Free(Region {
-                ptr: ptr.into(),
-                size: layout.size(),
-                align: layout.align(),
-            }));
+        let event = Event::Free(Region::from_layout(ptr.into(), layout));
+
+        // Note: the free is recorded before the region is handed back to the
+        // delegate, so that it's ordered before any other thread which is
+        // handed the same region records its allocation.
+        crate::with_recording_state(move |s| {
+            s.events.push(event);
+            track_memory(s, layout.size(), 0);
         });
+
+        self.delegate.dealloc(ptr, layout);
+        self.notify(event);
     }
 
     unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
-        let ptr = self.delegate.alloc_zeroed(layout);
+        if !crate::is_muted() && !self.is_tracked(layout.size()) {
+            return self.delegate.alloc_zeroed(layout);
+        }
+
+        if !crate::is_muted() && !self.check_budget(layout.size(), 0) {
+            return ptr::null_mut();
+        }
+
+        let ptr = if !crate::is_muted() && self.is_out_of_memory() {
+            ptr::null_mut()
+        } else {
+            self.delegate.alloc_zeroed(layout)
+        };
 
         // Note: On null return early, caller is likely to panic or handle OOM
         // scenario gracefully.
         if crate::is_muted() || ptr.is_null() {
             if ptr.is_null() {
-                crate::with_state(move |s| {
-                    s.borrow_mut().events.push(Event::AllocZeroedFailed);
+                crate::with_recording_state(|s| {
+                    s.events.push(Event::AllocZeroedFailed);
                 });
+                self.notify(Event::AllocZeroedFailed);
             }
 
             return ptr;
         }
 
-        crate::with_state(move |s| {
-            #[cfg(feature = "zeroed")]
-            let is_zeroed = Some(crate::utils::is_zeroed_ptr(ptr, layout.size()));
-            #[cfg(not(feature = "zeroed"))]
-            let is_zeroed = None;
-
-            s.borrow_mut().events.push(Event::AllocZeroed(AllocZeroed {
-                is_zeroed,
-                alloc: Region {
-                    ptr: ptr.into(),
-                    size: layout.size(),
-                    align: layout.align(),
-                },
-            }));
+        #[cfg(feature = "zeroed")]
+        let is_zeroed = Some(crate::utils::is_zeroed_ptr(ptr, layout.size()));
+        #[cfg(not(feature = "zeroed"))]
+        let is_zeroed = None;
+
+        let event = Event::AllocZeroed(AllocZeroed {
+            is_zeroed,
+            alloc: Region::from_layout(ptr.into(), layout),
+        });
+
+        crate::with_recording_state(move |s| {
+            s.events.push(event);
+            track_memory(s, 0, layout.size());
+
+            #[cfg(feature = "backtrace")]
+            self.capture_backtrace(s);
         });
 
+        self.notify(event);
         ptr
     }
 
@@ -132,14 +732,23 @@ where
         // scenario gracefully.
         if crate::is_muted() || ptr.is_null() {
             if ptr.is_null() {
-                crate::with_state(|s| {
-                    s.borrow_mut().events.push(Event::ReallocNull);
+                crate::with_recording_state(|s| {
+                    s.events.push(Event::ReallocNull);
                 });
+                self.notify(Event::ReallocNull);
             }
 
             return self.delegate.realloc(ptr, layout, new_size);
         }
 
+        if layout.size() < self.min_tracked_size && !self.is_tracked(new_size) {
+            return self.delegate.realloc(ptr, layout, new_size);
+        }
+
+        if !self.check_budget(new_size, layout.size()) {
+            return ptr::null_mut();
+        }
+
         // Safety Note: This needs to happen before call to `realloc`, since it
         // might deallocate it.
         #[cfg(feature = "realloc")]
@@ -150,44 +759,72 @@ where
         // Safety Note: Convert to pointer early to avoid relying on potentially
         // dangling pointer later.
         let old_ptr = ptr.into();
-        let new_ptr = self.delegate.realloc(ptr, layout, new_size);
+
+        // Note: while recording into a shard, the reallocation is performed as
+        // an allocation followed by a free. This way the event is ordered
+        // after the new region was handed to us, but before the old region
+        // can be handed to another thread.
+        let moving = crate::is_sharing();
+
+        let new_ptr = if self.is_out_of_memory() {
+            ptr::null_mut()
+        } else if moving {
+            let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+            let new_ptr = self.delegate.alloc(new_layout);
+
+            if !new_ptr.is_null() {
+                ptr::copy_nonoverlapping(ptr, new_ptr, usize::min(layout.size(), new_size));
+            }
+
+            new_ptr
+        } else {
+            self.delegate.realloc(ptr, layout, new_size)
+        };
 
         // Note: return early, caller is likely to panic or handle OOM scenario.
         // gracefully. Prior memory is unaltered.
         // TODO: Consider emitting diagnostics.
         if new_ptr.is_null() {
-            crate::with_state(|s| {
-                s.borrow_mut().events.push(Event::ReallocFailed);
+            crate::with_recording_state(|s| {
+                s.events.push(Event::ReallocFailed);
             });
+            self.notify(Event::ReallocFailed);
 
             return new_ptr;
         }
 
-        crate::with_state(move |s| {
-            #[cfg(feature = "realloc")]
-            let is_relocated = Some(old_hash == crate::utils::hash_ptr(new_ptr, min_size));
-            #[cfg(not(feature = "realloc"))]
-            let is_relocated = None;
-
-            let free = Region {
-                ptr: old_ptr,
-                size: layout.size(),
-                align: layout.align(),
-            };
-
-            let alloc = Region {
-                ptr: new_ptr.into(),
-                size: new_size,
-                align: layout.align(),
-            };
-
-            s.borrow_mut().events.push(Event::Realloc(Realloc {
-                is_relocated,
-                free,
-                alloc,
-            }));
+        #[cfg(feature = "realloc")]
+        let is_relocated = Some(old_hash == crate::utils::hash_ptr(new_ptr, min_size));
+        #[cfg(not(feature = "realloc"))]
+        let is_relocated = None;
+
+        let free = Region::from_layout(old_ptr, layout);
+
+        let alloc = Region {
+            ptr: new_ptr.into(),
+            size: new_size,
+            align: layout.align(),
+        };
+
+        let event = Event::Realloc(Realloc {
+            is_relocated,
+            free,
+            alloc,
         });
 
+        crate::with_recording_state(move |s| {
+            s.events.push(event);
+            track_memory(s, layout.size(), new_size);
+
+            #[cfg(feature = "backtrace")]
+            self.capture_backtrace(s);
+        });
+
+        if moving {
+            self.delegate.dealloc(ptr, layout);
+        }
+
+        self.notify(event);
         new_ptr
     }
 }
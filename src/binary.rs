@@ -0,0 +1,243 @@
+//! Compact binary encoding of snapshots.
+//!
+//! See [Snapshot::to_bytes] for a description of the format.
+
+use std::{convert::TryFrom, error, fmt};
+
+use crate::{AllocZeroed, BudgetExceeded, Event, Events, Pointer, Realloc, Region, Snapshot};
+
+/// Magic bytes which start every encoded snapshot.
+const MAGIC: [u8; 4] = *b"CHKR";
+
+/// The version of the format.
+const VERSION: u8 = 1;
+
+/// The size of the header in bytes.
+const HEADER_SIZE: usize = 4 + 1 + 8 + 1 + 8 + 8 + 8;
+
+/// The size of a single event record in bytes.
+const RECORD_SIZE: usize = 1 + 1 + 6 * 8;
+
+/// Error raised when decoding a snapshot with [Snapshot::from_bytes].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// The input doesn't start with the expected magic bytes.
+    InvalidMagic,
+    /// The input was encoded with an unsupported version of the format.
+    UnsupportedVersion {
+        /// The version found.
+        version: u8,
+    },
+    /// The input ended before the snapshot was complete.
+    UnexpectedEnd,
+    /// The input continues after the end of the snapshot.
+    TrailingBytes,
+    /// The record at the given index has an unknown kind of event.
+    InvalidKind {
+        /// The index of the record.
+        index: usize,
+        /// The kind found.
+        kind: u8,
+    },
+    /// The record at the given index has an invalid flag.
+    InvalidFlag {
+        /// The index of the record.
+        index: usize,
+        /// The flag found.
+        flag: u8,
+    },
+    /// A value doesn't fit in a `usize` on this platform.
+    Overflow,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMagic => write!(fmt, "Input is not an encoded snapshot"),
+            Self::UnsupportedVersion { version } => {
+                write!(fmt, "Unsupported format version {}", version)
+            }
+            Self::UnexpectedEnd => write!(fmt, "Unexpected end of input"),
+            Self::TrailingBytes => write!(fmt, "Trailing bytes after end of snapshot"),
+            Self::InvalidKind { index, kind } => {
+                write!(fmt, "Event {} has an invalid kind ({})", index, kind)
+            }
+            Self::InvalidFlag { index, flag } => {
+                write!(fmt, "Event {} has an invalid flag ({})", index, flag)
+            }
+            Self::Overflow => write!(fmt, "Value does not fit in a usize"),
+        }
+    }
+}
+
+impl error::Error for DecodeError {}
+
+/// Encode the given snapshot.
+pub(crate) fn encode(snapshot: &Snapshot) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER_SIZE + snapshot.events.len() * RECORD_SIZE);
+
+    out.extend_from_slice(&MAGIC);
+    out.push(VERSION);
+    put(&mut out, snapshot.peak_memory);
+    out.push(snapshot.max_bytes.is_some() as u8);
+    put(&mut out, snapshot.max_bytes.unwrap_or_default());
+    put(&mut out, snapshot.skipped_allocs);
+    put(&mut out, snapshot.events.len());
+
+    for event in snapshot.events.iter() {
+        let empty = Region::new(Pointer::new(), 0, 0);
+
+        let (kind, flag, a, b) = match *event {
+            Event::Alloc(region) => (0, None, region, empty),
+            Event::Free(region) => (1, None, region, empty),
+            Event::AllocZeroed(AllocZeroed { is_zeroed, alloc }) => (2, is_zeroed, alloc, empty),
+            Event::Realloc(Realloc {
+                is_relocated,
+                free,
+                alloc,
+            }) => (3, is_relocated, free, alloc),
+            Event::AllocFailed => (4, None, empty, empty),
+            Event::AllocZeroedFailed => (5, None, empty, empty),
+            Event::ReallocNull => (6, None, empty, empty),
+            Event::ReallocFailed => (7, None, empty, empty),
+            Event::BudgetExceeded(BudgetExceeded {
+                requested,
+                memory_used,
+                max_bytes,
+            }) => (
+                8,
+                None,
+                Region::new(Pointer::from(requested), memory_used, max_bytes),
+                empty,
+            ),
+            Event::InjectedFailure(region) => (9, None, region, empty),
+            Event::FreeByPtr(ptr) => (10, None, Region::new(ptr, 0, 0), empty),
+        };
+
+        out.push(kind);
+
+        out.push(match flag {
+            None => 0,
+            Some(false) => 1,
+            Some(true) => 2,
+        });
+
+        for region in [a, b] {
+            put(&mut out, region.ptr.0);
+            put(&mut out, region.size);
+            put(&mut out, region.align);
+        }
+    }
+
+    out
+}
+
+/// Decode a snapshot.
+pub(crate) fn decode(bytes: &[u8]) -> Result<Snapshot, DecodeError> {
+    let mut input = Input(bytes);
+
+    if input.take(MAGIC.len())? != MAGIC {
+        return Err(DecodeError::InvalidMagic);
+    }
+
+    let version = input.take(1)?[0];
+
+    if version != VERSION {
+        return Err(DecodeError::UnsupportedVersion { version });
+    }
+
+    let peak_memory = input.usize()?;
+    let has_max_bytes = input.take(1)?[0] != 0;
+    let max_bytes = input.usize()?;
+    let skipped_allocs = input.usize()?;
+    let len = input.usize()?;
+
+    // Note: guard against preallocating for a corrupt length.
+    if len > input.0.len() / RECORD_SIZE {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+
+    let mut events = Events::new();
+    events.reserve(len);
+
+    for index in 0..len {
+        let kind = input.take(1)?[0];
+
+        let flag = match input.take(1)?[0] {
+            0 => None,
+            1 => Some(false),
+            2 => Some(true),
+            flag => return Err(DecodeError::InvalidFlag { index, flag }),
+        };
+
+        let a = input.region()?;
+        let b = input.region()?;
+
+        let event = match kind {
+            0 => Event::Alloc(a),
+            1 => Event::Free(a),
+            2 => Event::AllocZeroed(AllocZeroed::new(flag, a)),
+            3 => Event::Realloc(Realloc::new(flag, a, b)),
+            4 => Event::AllocFailed,
+            5 => Event::AllocZeroedFailed,
+            6 => Event::ReallocNull,
+            7 => Event::ReallocFailed,
+            8 => Event::BudgetExceeded(BudgetExceeded::new(a.ptr.0, a.size, a.align)),
+            9 => Event::InjectedFailure(a),
+            10 => Event::FreeByPtr(a.ptr),
+            kind => return Err(DecodeError::InvalidKind { index, kind }),
+        };
+
+        events.push(event);
+    }
+
+    if !input.0.is_empty() {
+        return Err(DecodeError::TrailingBytes);
+    }
+
+    Ok(Snapshot {
+        events,
+        #[cfg(feature = "backtrace")]
+        backtraces: Default::default(),
+        peak_memory,
+        max_bytes: if has_max_bytes { Some(max_bytes) } else { None },
+        skipped_allocs,
+    })
+}
+
+/// Append a value as a little-endian 64-bit integer.
+fn put(out: &mut Vec<u8>, value: usize) {
+    out.extend_from_slice(&(value as u64).to_le_bytes());
+}
+
+/// The remaining input being decoded.
+struct Input<'a>(&'a [u8]);
+
+impl<'a> Input<'a> {
+    /// Take the given number of bytes from the input.
+    fn take(&mut self, n: usize) -> Result<&'a [u8], DecodeError> {
+        if self.0.len() < n {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    /// Take a little-endian 64-bit integer from the input.
+    fn usize(&mut self) -> Result<usize, DecodeError> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        usize::try_from(u64::from_le_bytes(bytes)).map_err(|_| DecodeError::Overflow)
+    }
+
+    /// Take a region from the input.
+    fn region(&mut self) -> Result<Region, DecodeError> {
+        let ptr = self.usize()?;
+        let size = self.usize()?;
+        let align = self.usize()?;
+        Ok(Region::new(Pointer::from(ptr), size, align))
+    }
+}
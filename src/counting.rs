@@ -0,0 +1,164 @@
+//! An allocator which only keeps running counters.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Allocator which keeps running counters of the allocations performed
+/// through it, without recording any events.
+///
+/// This is a lightweight alternative to [Allocator] for monitoring memory
+/// usage in release builds, where capturing every event would be too costly.
+/// Each call only updates a few atomic counters before calling `delegate`.
+///
+/// Every allocation is counted, regardless of whether it happens inside of
+/// [with] or while [muted]. Failed allocations aren't counted, and
+/// reallocations only adjust the number of bytes in use.
+///
+/// [Allocator]: crate::Allocator
+/// [with]: crate::with
+/// [muted]: crate::is_muted
+///
+/// # Examples
+///
+/// ```rust
+/// use checkers::CountingAllocator;
+///
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator::system();
+///
+/// let allocs = ALLOCATOR.allocs();
+/// let frees = ALLOCATOR.frees();
+///
+/// let a = vec![0u8; 1024];
+/// assert!(ALLOCATOR.memory_used() >= 1024);
+/// assert!(ALLOCATOR.peak_memory() >= 1024);
+/// drop(a);
+///
+/// assert!(ALLOCATOR.allocs() > allocs);
+/// assert!(ALLOCATOR.frees() > frees);
+/// ```
+pub struct CountingAllocator<T = System> {
+    delegate: T,
+    /// The number of allocations performed.
+    allocs: AtomicUsize,
+    /// The number of frees performed.
+    frees: AtomicUsize,
+    /// The number of bytes currently allocated.
+    memory_used: AtomicUsize,
+    /// The highest value `memory_used` has reached.
+    peak_memory: AtomicUsize,
+}
+
+impl<T> CountingAllocator<T> {
+    /// Construct an allocator which counts allocations performed through
+    /// `delegate`.
+    pub const fn new(delegate: T) -> Self {
+        Self {
+            delegate,
+            allocs: AtomicUsize::new(0),
+            frees: AtomicUsize::new(0),
+            memory_used: AtomicUsize::new(0),
+            peak_memory: AtomicUsize::new(0),
+        }
+    }
+
+    /// Access the delegate allocator.
+    pub fn delegate(&self) -> &T {
+        &self.delegate
+    }
+
+    /// Get the number of allocations performed, including zeroed ones.
+    pub fn allocs(&self) -> usize {
+        self.allocs.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of frees performed.
+    pub fn frees(&self) -> usize {
+        self.frees.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of bytes currently allocated.
+    pub fn memory_used(&self) -> usize {
+        self.memory_used.load(Ordering::Relaxed)
+    }
+
+    /// Get the highest number of bytes which have been allocated at once.
+    pub fn peak_memory(&self) -> usize {
+        self.peak_memory.load(Ordering::Relaxed)
+    }
+
+    /// Count `size` bytes as allocated.
+    fn grow(&self, size: usize) {
+        let used = self.memory_used.fetch_add(size, Ordering::Relaxed) + size;
+        self.peak_memory.fetch_max(used, Ordering::Relaxed);
+    }
+
+    /// Count `size` bytes as freed.
+    fn shrink(&self, size: usize) {
+        self.memory_used.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+impl CountingAllocator<System> {
+    /// Construct an allocator which counts allocations performed through the
+    /// system allocator.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #[global_allocator]
+    /// static ALLOCATOR: checkers::CountingAllocator = checkers::CountingAllocator::system();
+    /// ```
+    pub const fn system() -> Self {
+        Self::new(System)
+    }
+}
+
+unsafe impl<T> GlobalAlloc for CountingAllocator<T>
+where
+    T: GlobalAlloc,
+{
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.delegate.alloc(layout);
+
+        if !ptr.is_null() {
+            self.allocs.fetch_add(1, Ordering::Relaxed);
+            self.grow(layout.size());
+        }
+
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.delegate.dealloc(ptr, layout);
+        self.frees.fetch_add(1, Ordering::Relaxed);
+        self.shrink(layout.size());
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.delegate.alloc_zeroed(layout);
+
+        if !ptr.is_null() {
+            self.allocs.fetch_add(1, Ordering::Relaxed);
+            self.grow(layout.size());
+        }
+
+        ptr
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.delegate.realloc(ptr, layout, new_size);
+
+        if !new_ptr.is_null() {
+            if new_size > layout.size() {
+                self.grow(new_size - layout.size());
+            } else {
+                self.shrink(layout.size() - new_size);
+            }
+        }
+
+        new_ptr
+    }
+}
@@ -0,0 +1,69 @@
+//! CSV export of snapshots.
+//!
+//! See [Snapshot::to_csv] for a description of the columns.
+
+use std::{collections::BTreeMap, io};
+
+use crate::{AllocZeroed, Event, Pointer, Realloc, Snapshot};
+
+/// The header row of the export.
+const HEADER: &str = "index,kind,ptr,size,align,memory_used";
+
+/// Write the events of the given snapshot as CSV.
+pub(crate) fn write<W>(snapshot: &Snapshot, mut w: W) -> io::Result<()>
+where
+    W: io::Write,
+{
+    writeln!(w, "{}", HEADER)?;
+
+    let mut memory_used = 0usize;
+    // Sizes of live allocations, used to account for frees by pointer.
+    let mut live = BTreeMap::<Pointer, usize>::new();
+
+    for (index, event) in snapshot.events.iter().enumerate() {
+        let region = match *event {
+            Event::Alloc(region) => {
+                memory_used = memory_used.saturating_add(region.size);
+                live.insert(region.ptr, region.size);
+                Some(region)
+            }
+            Event::AllocZeroed(AllocZeroed { alloc, .. }) => {
+                memory_used = memory_used.saturating_add(alloc.size);
+                live.insert(alloc.ptr, alloc.size);
+                Some(alloc)
+            }
+            Event::Free(region) => {
+                memory_used = memory_used.saturating_sub(region.size);
+                live.remove(&region.ptr);
+                Some(region)
+            }
+            Event::FreeByPtr(ptr) => {
+                let size = live.remove(&ptr).unwrap_or_default();
+                memory_used = memory_used.saturating_sub(size);
+                write!(w, "{},{},{:#x},,,", index, event.kind(), ptr)?;
+                writeln!(w, "{}", memory_used)?;
+                continue;
+            }
+            Event::Realloc(Realloc { free, alloc, .. }) => {
+                memory_used = memory_used.saturating_sub(free.size);
+                memory_used = memory_used.saturating_add(alloc.size);
+                live.remove(&free.ptr);
+                live.insert(alloc.ptr, alloc.size);
+                Some(alloc)
+            }
+            Event::InjectedFailure(region) => Some(region),
+            _ => None,
+        };
+
+        write!(w, "{},{},", index, event.kind())?;
+
+        match region {
+            Some(region) => write!(w, "{:#x},{},{},", region.ptr, region.size, region.align)?,
+            None => write!(w, ",,,")?,
+        }
+
+        writeln!(w, "{}", memory_used)?;
+    }
+
+    Ok(())
+}
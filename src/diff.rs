@@ -0,0 +1,63 @@
+//! Differences between snapshots.
+
+use std::collections::BTreeMap;
+
+use crate::Snapshot;
+
+/// The difference between two snapshots, as produced by [Snapshot::diff].
+///
+/// Each field is the value of the other snapshot minus the value of the
+/// snapshot being diffed against, so positive values mean that the other
+/// snapshot has more.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SnapshotDiff {
+    /// Difference in the number of allocations.
+    pub allocs: isize,
+    /// Difference in the number of frees.
+    pub frees: isize,
+    /// Difference in the peak memory used.
+    pub peak_memory: isize,
+    /// Difference in the total number of bytes allocated.
+    pub total_allocated: isize,
+    /// Difference in the number of allocations per power-of-two size bucket,
+    /// keyed as in [Events::size_histogram_pow2]. Buckets which didn't change
+    /// are omitted.
+    ///
+    /// [Events::size_histogram_pow2]: crate::Events::size_histogram_pow2
+    pub size_buckets: BTreeMap<usize, isize>,
+}
+
+impl SnapshotDiff {
+    pub(crate) fn new(from: &Snapshot, to: &Snapshot) -> Self {
+        let mut size_buckets = BTreeMap::new();
+
+        for (bucket, count) in from.events.size_histogram_pow2() {
+            *size_buckets.entry(bucket).or_default() -= count as isize;
+        }
+
+        for (bucket, count) in to.events.size_histogram_pow2() {
+            *size_buckets.entry(bucket).or_default() += count as isize;
+        }
+
+        size_buckets.retain(|_, delta| *delta != 0);
+
+        Self {
+            allocs: delta(from.events.allocs(), to.events.allocs()),
+            frees: delta(from.events.frees(), to.events.frees()),
+            peak_memory: delta(from.peak_memory, to.peak_memory),
+            total_allocated: delta(from.events.total_allocated(), to.events.total_allocated()),
+            size_buckets,
+        }
+    }
+
+    /// Test if there are no differences.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Compute the signed difference `to - from`.
+fn delta(from: usize, to: usize) -> isize {
+    (to as isize).wrapping_sub(from as isize)
+}
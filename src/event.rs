@@ -1,15 +1,21 @@
 //! A single allocator event.
 
-use crate::{AllocZeroed, Realloc, Region};
+use crate::{AllocZeroed, BudgetExceeded, Pointer, Realloc, Region};
 
 /// Metadata for a single allocation or deallocation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[non_exhaustive]
 pub enum Event {
     /// An allocation.
     Alloc(Region),
     /// A deallocation.
     Free(Region),
+    /// A deallocation which only specifies the pointer being freed, like the
+    /// C `free` function. It frees whichever region was allocated at the
+    /// pointer.
+    FreeByPtr(Pointer),
     /// A zerod allocation, with an optional boolean indicates if it is actually
     /// zeroed or not.
     AllocZeroed(AllocZeroed),
@@ -25,6 +31,14 @@ pub enum Event {
     /// A reallocation failed (produced null), and the previous region is left
     /// unchanged.
     ReallocFailed,
+    /// An allocation or reallocation was refused (produced null) since it
+    /// would exceed the memory budget of the allocator.
+    BudgetExceeded(BudgetExceeded),
+    /// An allocation of the given region was made to fail on purpose by
+    /// [RandomFailingAllocator]. The pointer of the region is null.
+    ///
+    /// [RandomFailingAllocator]: crate::RandomFailingAllocator
+    InjectedFailure(Region),
 }
 
 impl Event {
@@ -118,6 +132,47 @@ impl Event {
         }
     }
 
+    /// Move every pointer in the event from being relative to `old_base` to
+    /// being relative to `new_base`, see [Region::rebase].
+    #[cfg(feature = "std")]
+    pub(crate) fn rebase(self, old_base: Pointer, new_base: Pointer) -> Self {
+        let rebase = |region: Region| region.rebase(old_base, new_base);
+
+        match self {
+            Self::Alloc(region) => Self::Alloc(rebase(region)),
+            Self::Free(region) => Self::Free(rebase(region)),
+            Self::FreeByPtr(ptr) => Self::FreeByPtr(ptr.rebase(old_base, new_base)),
+            Self::AllocZeroed(AllocZeroed { is_zeroed, alloc }) => {
+                Self::AllocZeroed(AllocZeroed::new(is_zeroed, rebase(alloc)))
+            }
+            Self::Realloc(Realloc {
+                is_relocated,
+                free,
+                alloc,
+            }) => Self::Realloc(Realloc::new(is_relocated, rebase(free), rebase(alloc))),
+            // Note: injected failures have no pointer to rebase.
+            event => event,
+        }
+    }
+
+    /// Get the name of the kind of this event, as used in exports.
+    #[cfg(feature = "std")]
+    pub(crate) fn kind(self) -> &'static str {
+        match self {
+            Self::Alloc(..) => "alloc",
+            Self::Free(..) => "free",
+            Self::FreeByPtr(..) => "free_by_ptr",
+            Self::AllocZeroed(..) => "alloc_zeroed",
+            Self::Realloc(..) => "realloc",
+            Self::AllocFailed => "alloc_failed",
+            Self::AllocZeroedFailed => "alloc_zeroed_failed",
+            Self::ReallocNull => "realloc_null",
+            Self::ReallocFailed => "realloc_failed",
+            Self::BudgetExceeded(..) => "budget_exceeded",
+            Self::InjectedFailure(..) => "injected_failure",
+        }
+    }
+
     /// Test if this event is an allocation which matches the specified
     /// predicate.
     ///
@@ -129,13 +184,16 @@ impl Event {
     /// assert!(Event::AllocFailed.is_failed());
     /// assert!(Event::AllocZeroedFailed.is_failed());
     /// assert!(Event::ReallocFailed.is_failed());
+    /// assert!(Event::InjectedFailure(checkers::Region::new(0.into(), 10, 1)).is_failed());
     /// ```
     pub fn is_failed(self) -> bool {
-        match self {
+        matches!(
+            self,
             Self::AllocFailed { .. }
-            | Self::AllocZeroedFailed { .. }
-            | Self::ReallocFailed { .. } => true,
-            _ => false,
-        }
+                | Self::AllocZeroedFailed { .. }
+                | Self::ReallocFailed { .. }
+                | Self::BudgetExceeded { .. }
+                | Self::InjectedFailure { .. }
+        )
     }
 }
@@ -2,9 +2,17 @@
 //!
 //! We use a wrapper type to provide convenience methods for diagnostics.
 
-use std::{ops, slice};
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::{ops, slice};
+#[cfg(feature = "std")]
+use std::thread::{self, ThreadId};
+#[cfg(feature = "timestamps")]
+use std::time::{Duration, Instant};
 
-use crate::{Event, Machine, Violation};
+use crate::{AllocZeroed, Event, Machine, Pointer, Realloc, Region, Violation};
+
+/// Live regions, together with the index of the event which allocated them.
+type LiveRegions = BTreeMap<Pointer, (Region, usize)>;
 
 /// Collections of events.
 ///
@@ -12,12 +20,35 @@ use crate::{Event, Machine, Violation};
 #[derive(Debug, Clone)]
 pub struct Events {
     data: Vec<Event>,
+    /// The thread that recorded each event in `data`.
+    #[cfg(feature = "std")]
+    threads: Vec<ThreadId>,
+    /// The tag of each event in `data`.
+    #[cfg(feature = "std")]
+    tags: Vec<Option<&'static str>>,
+    /// The instant that timestamps are relative to.
+    #[cfg(feature = "timestamps")]
+    start: Option<Instant>,
+    /// The time at which each event in `data` was recorded, relative to
+    /// `start`.
+    #[cfg(feature = "timestamps")]
+    times: Vec<Duration>,
 }
 
 impl Events {
     /// Construct a new collection of allocations.
     pub const fn new() -> Self {
-        Self { data: Vec::new() }
+        Self {
+            data: Vec::new(),
+            #[cfg(feature = "std")]
+            threads: Vec::new(),
+            #[cfg(feature = "std")]
+            tags: Vec::new(),
+            #[cfg(feature = "timestamps")]
+            start: None,
+            #[cfg(feature = "timestamps")]
+            times: Vec::new(),
+        }
     }
 
     /// Get the number of events in this collection.
@@ -38,9 +69,38 @@ impl Events {
     /// Reserve extra capacity for the underlying storage.
     pub fn reserve(&mut self, cap: usize) {
         self.data.reserve(cap.saturating_sub(self.data.capacity()));
+        #[cfg(feature = "std")]
+        self.threads
+            .reserve(cap.saturating_sub(self.threads.capacity()));
+        #[cfg(feature = "std")]
+        self.tags.reserve(cap.saturating_sub(self.tags.capacity()));
+        #[cfg(feature = "timestamps")]
+        self.times
+            .reserve(cap.saturating_sub(self.times.capacity()));
     }
 
     /// Fetch all allocations as a slice.
+    ///
+    /// Events can also be indexed directly by position or by range, which
+    /// panics on out-of-range indices just like indexing a slice does.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Events, Event, Region};
+    /// let mut events = Events::new();
+    ///
+    /// for n in 0..5 {
+    ///     events.push(Event::Alloc(Region::new((n * 10).into(), 10, 1)));
+    /// }
+    ///
+    /// assert_eq!(5, events.as_slice().len());
+    /// assert_eq!(&events.as_slice()[2..4], &events[2..4]);
+    /// assert_eq!(Event::Alloc(Region::new(20.into(), 10, 1)), events[2..4][0]);
+    ///
+    /// let result = std::panic::catch_unwind(|| events[4..6].len());
+    /// assert!(result.is_err());
+    /// ```
     pub fn as_slice(&self) -> &[Event] {
         ops::Deref::deref(self)
     }
@@ -53,10 +113,87 @@ impl Events {
     /// Clear the collection of events.
     pub fn clear(&mut self) {
         self.data.clear();
+        #[cfg(feature = "std")]
+        self.threads.clear();
+        #[cfg(feature = "std")]
+        self.tags.clear();
+        #[cfg(feature = "timestamps")]
+        {
+            self.start = None;
+            self.times.clear();
+        }
+    }
+
+    /// Start the clock that timestamps are relative to.
+    #[cfg(feature = "timestamps")]
+    pub(crate) fn start_clock(&mut self) {
+        self.start = Some(Instant::now());
+    }
+
+    /// Start the clock that timestamps are relative to at the given instant.
+    #[cfg(feature = "timestamps")]
+    pub(crate) fn start_clock_at(&mut self, start: Instant) {
+        self.start = Some(start);
+    }
+
+    /// Get the time elapsed since the clock was started.
+    #[cfg(feature = "timestamps")]
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.start.map(|start| start.elapsed()).unwrap_or_default()
+    }
+
+    /// Make all timestamps relative to `offset` after the current start of the
+    /// clock.
+    #[cfg(feature = "timestamps")]
+    pub(crate) fn rebased(mut self, offset: Duration) -> Self {
+        self.start = self.start.map(|start| start + offset);
+
+        for time in &mut self.times {
+            *time = time.saturating_sub(offset);
+        }
+
+        self
+    }
+
+    /// Copy all events starting at the given index into a new collection.
+    #[cfg(feature = "std")]
+    pub(crate) fn tail(&self, start: usize) -> Self {
+        let start = usize::min(start, self.data.len());
+
+        Self {
+            data: self.data[start..].to_vec(),
+            threads: self.threads[start..].to_vec(),
+            tags: self.tags[start..].to_vec(),
+            #[cfg(feature = "timestamps")]
+            start: self.start,
+            #[cfg(feature = "timestamps")]
+            times: self.times[start..].to_vec(),
+        }
+    }
+
+    /// Copy the event at `index` in `other` into this collection, keeping the
+    /// thread, tag and timestamp it was recorded with.
+    ///
+    /// Timestamps are copied as is, so both collections need to use the same
+    /// clock.
+    #[cfg(feature = "std")]
+    pub(crate) fn push_from(&mut self, other: &Events, index: usize) {
+        let _g = crate::mute_guard(true);
+
+        self.data.push(other.data[index]);
+        self.threads.push(other.threads[index]);
+        self.tags.push(other.tags[index]);
+
+        #[cfg(feature = "timestamps")]
+        self.times.push(other.times[index]);
     }
 
     /// Push a single event into the collection of events.
     ///
+    /// With the `std` feature enabled, the event is attributed to the current
+    /// thread, see [Events::thread_id], and tagged with its current tag, see
+    /// [Events::tag].
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -69,12 +206,336 @@ impl Events {
     pub fn push(&mut self, event: Event) {
         // Note: pushing into an at-capacity collection would allocate, so we
         // take care of it here, while muting the tracker.
+        #[cfg(feature = "std")]
+        let _g = crate::mute_guard(true);
+
         if self.data.capacity() == self.data.len() {
-            let _g = crate::mute_guard(true);
             self.data.reserve(1);
         }
 
         self.data.push(event);
+
+        #[cfg(feature = "std")]
+        {
+            if self.threads.capacity() == self.threads.len() {
+                self.threads.reserve(1);
+            }
+
+            self.threads.push(thread::current().id());
+
+            if self.tags.capacity() == self.tags.len() {
+                self.tags.reserve(1);
+            }
+
+            self.tags.push(crate::current_tag());
+        }
+
+        #[cfg(feature = "timestamps")]
+        {
+            let time = self.start.get_or_insert_with(Instant::now).elapsed();
+
+            if self.times.capacity() == self.times.len() {
+                self.times.reserve(1);
+            }
+
+            self.times.push(time);
+        }
+    }
+
+    /// Get the id of the thread which recorded the event at the given index.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Events, Event, Region};
+    /// let mut events = Events::new();
+    /// events.push(Event::Alloc(Region::new(10.into(), 10, 1)));
+    /// assert_eq!(Some(std::thread::current().id()), events.thread_id(0));
+    /// assert_eq!(None, events.thread_id(1));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn thread_id(&self, index: usize) -> Option<ThreadId> {
+        self.threads.get(index).copied()
+    }
+
+    /// Get the tag of the event at the given index, if it was recorded within
+    /// [with_tag][crate::with_tag].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Events, Event, Region};
+    /// let mut events = Events::new();
+    /// events.push(Event::Alloc(Region::new(10.into(), 10, 1)));
+    /// checkers::with_tag("parser", || events.push(Event::Alloc(Region::new(20.into(), 10, 1))));
+    ///
+    /// assert_eq!(None, events.tag(0));
+    /// assert_eq!(Some("parser"), events.tag(1));
+    /// assert_eq!(None, events.tag(2));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn tag(&self, index: usize) -> Option<&'static str> {
+        self.tags.get(index).copied().flatten()
+    }
+
+    /// Iterate over all events with the given tag, together with their index.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Events, Event, Region};
+    /// let mut events = Events::new();
+    /// let event = Event::Alloc(Region::new(10.into(), 10, 1));
+    /// checkers::with_tag("parser", || events.push(event));
+    /// events.push(Event::Free(Region::new(10.into(), 10, 1)));
+    ///
+    /// assert_eq!(vec![(0, event)], events.by_tag("parser").collect::<Vec<_>>());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn by_tag<'a>(&'a self, label: &'a str) -> impl Iterator<Item = (usize, Event)> + 'a {
+        self.data
+            .iter()
+            .zip(self.tags.iter())
+            .enumerate()
+            .filter(move |(_, (_, t))| **t == Some(label))
+            .map(|(index, (event, _))| (index, *event))
+    }
+
+    /// Get the time at which the event at the given index was recorded,
+    /// relative to the start of the snapshot it was recorded in.
+    ///
+    /// Events recorded outside of a snapshot are relative to the first event.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Events, Event, Region};
+    /// let mut events = Events::new();
+    /// events.push(Event::Alloc(Region::new(10.into(), 10, 1)));
+    /// events.push(Event::Free(Region::new(10.into(), 10, 1)));
+    ///
+    /// assert!(events.timestamp(0).unwrap() <= events.timestamp(1).unwrap());
+    /// assert_eq!(None, events.timestamp(2));
+    /// ```
+    #[cfg(feature = "timestamps")]
+    pub fn timestamp(&self, index: usize) -> Option<Duration> {
+        self.times.get(index).copied()
+    }
+
+    /// Count the number of allocations in every consecutive `interval` of
+    /// time, starting at the start of the snapshot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Events, Event, Region};
+    /// use std::time::Duration;
+    ///
+    /// let mut events = Events::new();
+    /// events.push(Event::Alloc(Region::new(10.into(), 10, 1)));
+    /// events.push(Event::Alloc(Region::new(20.into(), 10, 1)));
+    ///
+    /// let rate = events.allocs_over_time(Duration::from_secs(3600));
+    /// assert_eq!(vec![2], rate);
+    /// ```
+    #[cfg(feature = "timestamps")]
+    pub fn allocs_over_time(&self, interval: Duration) -> Vec<usize> {
+        assert!(interval > Duration::default(), "interval must be non-zero");
+
+        let mut buckets = Vec::new();
+
+        for (event, time) in self.data.iter().zip(self.times.iter()) {
+            if let Event::Alloc(..) | Event::AllocZeroed(..) = event {
+                let bucket = (time.as_nanos() / interval.as_nanos()) as usize;
+
+                if buckets.len() <= bucket {
+                    buckets.resize(bucket + 1, 0);
+                }
+
+                buckets[bucket] += 1;
+            }
+        }
+
+        buckets
+    }
+
+    /// Iterate over all events recorded by the given thread, together with
+    /// their index.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Events, Event, Region};
+    /// let mut events = Events::new();
+    /// let event = Event::Alloc(Region::new(10.into(), 10, 1));
+    /// events.push(event);
+    ///
+    /// let current = std::thread::current().id();
+    /// assert_eq!(vec![(0, event)], events.by_thread(current).collect::<Vec<_>>());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn by_thread(&self, thread: ThreadId) -> impl Iterator<Item = (usize, Event)> + '_ {
+        self.data
+            .iter()
+            .zip(self.threads.iter())
+            .enumerate()
+            .filter(move |(_, (_, t))| **t == thread)
+            .map(|(index, (event, _))| (index, *event))
+    }
+
+    /// Get the lifetime of every region which was freed, in the order they
+    /// were freed.
+    ///
+    /// The lifetime of a region is the number of events from its allocation
+    /// up to and including its free, using the index of each event as its
+    /// sequence number. Reallocations count as both a free of the old region
+    /// and an allocation of the new one.
+    ///
+    /// See [Events::live_lifetimes] for regions which are still live.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Events, Event, Region};
+    ///
+    /// let a = Region::new(10.into(), 10, 1);
+    /// let b = Region::new(20.into(), 10, 1);
+    ///
+    /// let mut events = Events::new();
+    /// events.push(Event::Alloc(a));
+    /// events.push(Event::Alloc(b));
+    /// events.push(Event::Free(b));
+    /// events.push(Event::Free(a));
+    ///
+    /// assert_eq!(vec![(b, 1), (a, 3)], events.lifetimes());
+    /// ```
+    pub fn lifetimes(&self) -> Vec<(Region, usize)> {
+        self.collect_lifetimes().0
+    }
+
+    /// Get the number of events recorded since every region which is still
+    /// live was allocated, in the order they were allocated.
+    ///
+    /// See [Events::lifetimes].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Events, Event, Region};
+    ///
+    /// let a = Region::new(10.into(), 10, 1);
+    /// let b = Region::new(20.into(), 10, 1);
+    ///
+    /// let mut events = Events::new();
+    /// events.push(Event::Alloc(a));
+    /// events.push(Event::Alloc(b));
+    /// events.push(Event::Free(b));
+    ///
+    /// assert_eq!(vec![(a, 2)], events.live_lifetimes());
+    /// ```
+    pub fn live_lifetimes(&self) -> Vec<(Region, usize)> {
+        let mut live = self
+            .collect_lifetimes()
+            .1
+            .into_iter()
+            .map(|(_, (region, index))| (index, region))
+            .collect::<Vec<_>>();
+
+        live.sort_by_key(|&(index, _)| index);
+
+        let last = self.data.len().saturating_sub(1);
+
+        live.into_iter()
+            .map(|(index, region)| (region, last - index))
+            .collect()
+    }
+
+    /// Collect the lifetimes of freed regions, and the regions which are still
+    /// live together with the index they were allocated at.
+    fn collect_lifetimes(&self) -> (Vec<(Region, usize)>, LiveRegions) {
+        let mut live = LiveRegions::new();
+        let mut freed = Vec::new();
+
+        let mut free = |live: &mut LiveRegions, ptr: Pointer, index| {
+            if let Some((region, allocated)) = live.remove(&ptr) {
+                freed.push((region, index - allocated));
+            }
+        };
+
+        for (index, event) in self.data.iter().enumerate() {
+            match *event {
+                Event::Alloc(region) | Event::AllocZeroed(AllocZeroed { alloc: region, .. }) => {
+                    live.insert(region.ptr, (region, index));
+                }
+                Event::Free(region) => {
+                    free(&mut live, region.ptr, index);
+                }
+                Event::FreeByPtr(ptr) => {
+                    free(&mut live, ptr, index);
+                }
+                Event::Realloc(Realloc {
+                    free: region,
+                    alloc,
+                    ..
+                }) => {
+                    free(&mut live, region.ptr, index);
+                    live.insert(alloc.ptr, (alloc, index));
+                }
+                _ => (),
+            }
+        }
+
+        (freed, live)
+    }
+
+    /// Find all regions which were freed on a different thread than the one
+    /// they were allocated on.
+    ///
+    /// Each entry contains the freed region, the thread it was allocated on,
+    /// and the thread it was freed on. Reallocations count as both a free of
+    /// the old region and an allocation of the new one.
+    #[cfg(feature = "std")]
+    pub fn foreign_frees(&self) -> Vec<(Region, ThreadId, ThreadId)> {
+        let mut live = BTreeMap::<Pointer, (Region, ThreadId)>::new();
+        let mut foreign = Vec::new();
+
+        // Note: frees by pointer report the region which was allocated.
+        let mut free = |live: &mut BTreeMap<_, _>, ptr, region: Option<Region>, thread| {
+            if let Some((allocated, allocated_by)) = live.remove(&ptr) {
+                if allocated_by != thread {
+                    foreign.push((region.unwrap_or(allocated), allocated_by, thread));
+                }
+            }
+        };
+
+        for (event, &thread) in self.data.iter().zip(self.threads.iter()) {
+            match *event {
+                Event::Alloc(region) | Event::AllocZeroed(AllocZeroed { alloc: region, .. }) => {
+                    live.insert(region.ptr, (region, thread));
+                }
+                Event::Free(region) => {
+                    free(&mut live, region.ptr, Some(region), thread);
+                }
+                Event::FreeByPtr(ptr) => {
+                    free(&mut live, ptr, None, thread);
+                }
+                Event::Realloc(Realloc {
+                    free: region,
+                    alloc,
+                    ..
+                }) => {
+                    free(&mut live, region.ptr, Some(region), thread);
+                    live.insert(alloc.ptr, (alloc, thread));
+                }
+                _ => (),
+            }
+        }
+
+        foreign
     }
 
     /// Count the number of allocations in this collection of events.
@@ -98,7 +559,7 @@ impl Events {
             .sum()
     }
 
-    /// Count the number of allocations in this collection of events.
+    /// Count the number of reallocations in this collection of events.
     ///
     /// # Examples
     ///
@@ -141,12 +602,226 @@ impl Events {
         self.data
             .iter()
             .map(|e| match e {
-                Event::Free { .. } => 1,
+                Event::Free { .. } | Event::FreeByPtr { .. } => 1,
                 _ => 0,
             })
             .sum()
     }
 
+    /// Count the total number of bytes allocated in this collection of events.
+    ///
+    /// Reallocations only count the number of bytes they grow a region by.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Events, Event, Region, Realloc};
+    /// let mut events = Events::new();
+    /// events.push(Event::Alloc(Region::new(10.into(), 10, 1)));
+    /// events.push(Event::Realloc(Realloc::new(
+    ///     None,
+    ///     Region::new(10.into(), 10, 1),
+    ///     Region::new(10.into(), 15, 1),
+    /// )));
+    /// events.push(Event::Free(Region::new(10.into(), 15, 1)));
+    /// assert_eq!(15, events.total_allocated());
+    /// ```
+    pub fn total_allocated(&self) -> usize {
+        self.data
+            .iter()
+            .map(|e| match *e {
+                Event::Alloc(region) | Event::AllocZeroed(AllocZeroed { alloc: region, .. }) => {
+                    region.size
+                }
+                Event::Realloc(Realloc { free, alloc, .. }) => alloc.size.saturating_sub(free.size),
+                _ => 0,
+            })
+            .fold(0, usize::saturating_add)
+    }
+
+    /// Compute the average size of allocations, as the
+    /// [total number of bytes allocated][Events::total_allocated] divided by
+    /// the [number of allocations][Events::allocs]. Returns `None` if there
+    /// are no allocations.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Events, Event, Region};
+    /// let mut events = Events::new();
+    /// assert_eq!(None, events.average_alloc_size());
+    ///
+    /// events.push(Event::Alloc(Region::new(10.into(), 10, 1)));
+    /// events.push(Event::Alloc(Region::new(20.into(), 20, 1)));
+    /// events.push(Event::Free(Region::new(10.into(), 10, 1)));
+    /// assert_eq!(Some(15.0), events.average_alloc_size());
+    /// ```
+    pub fn average_alloc_size(&self) -> Option<f64> {
+        match self.allocs() {
+            0 => None,
+            allocs => Some(self.total_allocated() as f64 / allocs as f64),
+        }
+    }
+
+    /// Compute the median size of allocations. If there's an even number of
+    /// allocations, this is the average of the two middle sizes. Returns
+    /// `None` if there are no allocations.
+    ///
+    /// Like [Events::allocs], reallocations are not counted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Events, Event, Region};
+    /// let mut events = Events::new();
+    /// assert_eq!(None, events.median_alloc_size());
+    ///
+    /// events.push(Event::Alloc(Region::new(10.into(), 1000, 1)));
+    /// events.push(Event::Alloc(Region::new(2000.into(), 10, 1)));
+    /// events.push(Event::Alloc(Region::new(3000.into(), 20, 1)));
+    /// assert_eq!(Some(20.0), events.median_alloc_size());
+    ///
+    /// events.push(Event::Alloc(Region::new(4000.into(), 30, 1)));
+    /// assert_eq!(Some(25.0), events.median_alloc_size());
+    /// ```
+    pub fn median_alloc_size(&self) -> Option<f64> {
+        let mut sizes =
+            self.data
+                .iter()
+                .filter_map(|e| match *e {
+                    Event::Alloc(region)
+                    | Event::AllocZeroed(AllocZeroed { alloc: region, .. }) => Some(region.size),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+
+        if sizes.is_empty() {
+            return None;
+        }
+
+        sizes.sort_unstable();
+        let middle = sizes.len() / 2;
+
+        if sizes.len() % 2 == 0 {
+            Some((sizes[middle - 1] as f64 + sizes[middle] as f64) / 2.0)
+        } else {
+            Some(sizes[middle] as f64)
+        }
+    }
+
+    /// Build a histogram mapping each allocation size to the number of
+    /// allocations of that size.
+    ///
+    /// Reallocations count towards the size of the region they allocate. The
+    /// size of the region they free was already counted when it was
+    /// allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Events, Event, Region, Realloc};
+    /// let mut events = Events::new();
+    /// events.push(Event::Alloc(Region::new(10.into(), 24, 1)));
+    /// events.push(Event::Alloc(Region::new(40.into(), 24, 1)));
+    /// events.push(Event::Realloc(Realloc::new(
+    ///     None,
+    ///     Region::new(40.into(), 24, 1),
+    ///     Region::new(40.into(), 48, 1),
+    /// )));
+    ///
+    /// let histogram = events.size_histogram();
+    /// assert_eq!(Some(&2), histogram.get(&24));
+    /// assert_eq!(Some(&1), histogram.get(&48));
+    /// ```
+    pub fn size_histogram(&self) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+
+        for size in self.alloc_sizes() {
+            *histogram.entry(size).or_default() += 1;
+        }
+
+        histogram
+    }
+
+    /// Build a histogram of allocation sizes grouped into power-of-two
+    /// buckets. Each bucket is keyed by its inclusive lower bound, so that the
+    /// key `n` counts allocations with sizes in `[n, 2n)`.
+    ///
+    /// Reallocations are counted like in [Events::size_histogram].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Events, Event, Region};
+    /// let mut events = Events::new();
+    /// events.push(Event::Alloc(Region::new(10.into(), 16, 1)));
+    /// events.push(Event::Alloc(Region::new(40.into(), 24, 1)));
+    /// events.push(Event::Alloc(Region::new(80.into(), 32, 1)));
+    ///
+    /// let histogram = events.size_histogram_pow2();
+    /// assert_eq!(Some(&2), histogram.get(&16));
+    /// assert_eq!(Some(&1), histogram.get(&32));
+    /// ```
+    pub fn size_histogram_pow2(&self) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+
+        for size in self.alloc_sizes() {
+            let bucket = match size {
+                0 => 0,
+                size => 1 << (usize::BITS - 1 - size.leading_zeros()),
+            };
+
+            *histogram.entry(bucket).or_default() += 1;
+        }
+
+        histogram
+    }
+
+    /// Build a histogram mapping each requested alignment to the number of
+    /// allocations requesting it.
+    ///
+    /// Reallocations are counted like in [Events::size_histogram].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Events, Event, Region};
+    /// let mut events = Events::new();
+    /// events.push(Event::Alloc(Region::new(64.into(), 16, 64)));
+    /// events.push(Event::Alloc(Region::new(128.into(), 16, 64)));
+    /// events.push(Event::Alloc(Region::new(200.into(), 16, 8)));
+    /// events.push(Event::Free(Region::new(200.into(), 16, 8)));
+    ///
+    /// let histogram = events.align_histogram();
+    /// assert_eq!(Some(&2), histogram.get(&64));
+    /// assert_eq!(Some(&1), histogram.get(&8));
+    /// assert_eq!(2, histogram.len());
+    /// ```
+    pub fn align_histogram(&self) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+
+        for region in self.alloc_regions() {
+            *histogram.entry(region.align).or_default() += 1;
+        }
+
+        histogram
+    }
+
+    /// Iterate over the sizes of all allocated regions.
+    fn alloc_sizes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.alloc_regions().map(|region| region.size)
+    }
+
+    /// Iterate over all allocated regions.
+    fn alloc_regions(&self) -> impl Iterator<Item = Region> + '_ {
+        self.data.iter().filter_map(|e| match *e {
+            Event::Alloc(region)
+            | Event::AllocZeroed(AllocZeroed { alloc: region, .. })
+            | Event::Realloc(Realloc { alloc: region, .. }) => Some(region),
+            _ => None,
+        })
+    }
+
     /// Validate the current state and populate the errors collection with any
     /// violations found.
     ///
@@ -155,15 +830,10 @@ impl Events {
     pub fn validate(&self, errors: &mut Vec<Violation>) {
         let mut machine = Machine::default();
 
-        for event in self.as_slice() {
-            if let Err(e) = machine.push(*event) {
-                errors.push(e);
-            }
-        }
+        let violations = machine.push_all(self.as_slice().iter().copied());
+        errors.extend(violations.into_iter().map(|(_, e)| e));
 
-        for region in machine.trailing_regions() {
-            errors.push(Violation::Leaked { region });
-        }
+        errors.extend(machine.leaks());
     }
 
     /// Max amount of memory used according to this event history.
@@ -183,14 +853,49 @@ impl Events {
     pub fn max_memory_used(&self) -> Result<usize, Violation> {
         let mut machine = Machine::default();
 
-        let mut max = 0usize;
-
         for event in self.as_slice() {
             machine.push(*event)?;
-            max = usize::max(machine.memory_used, max);
         }
 
-        Ok(max)
+        Ok(machine.peak_memory())
+    }
+
+    /// Get the number of bytes in use after each event in this history.
+    ///
+    /// The events are replayed through a fresh [Machine], so memory in use
+    /// follows [Machine::push]. Events it rejects leave the amount of memory
+    /// in use unchanged, while an allocation which raises
+    /// [NonZeroedAlloc][Violation::NonZeroedAlloc] is still recorded and
+    /// counted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Events, Event, Region};
+    /// let mut events = Events::new();
+    /// events.push(Event::Alloc(Region::new(0x10.into(), 0x10, 1)));
+    /// events.push(Event::Alloc(Region::new(0x20.into(), 0x20, 1)));
+    /// events.push(Event::Free(Region::new(0x10.into(), 0x10, 1)));
+    /// events.push(Event::Free(Region::new(0x10.into(), 0x10, 1)));
+    /// events.push(Event::Free(Region::new(0x20.into(), 0x20, 1)));
+    /// assert_eq!(vec![0x10, 0x30, 0x20, 0x20, 0], events.memory_timeline());
+    /// ```
+    pub fn memory_timeline(&self) -> Vec<usize> {
+        let mut machine = Machine::default();
+
+        self.as_slice()
+            .iter()
+            .map(|event| {
+                let _ = machine.push(*event);
+                machine.memory_used()
+            })
+            .collect()
+    }
+}
+
+impl Default for Events {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -213,6 +918,6 @@ impl<I: slice::SliceIndex<[Event]>> ops::Index<I> for Events {
 
     #[inline]
     fn index(&self, index: I) -> &Self::Output {
-        std::ops::Index::index(&self.data, index)
+        ops::Index::index(&self.data, index)
     }
 }
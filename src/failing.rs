@@ -0,0 +1,264 @@
+//! An allocator which injects allocation failures.
+
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    ptr,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
+
+use crate::{Event, Pointer, Region};
+
+/// Allocator which simulates running out of memory after a configured number
+/// of successful allocations.
+///
+/// It is intended to be used as the delegate of [Allocator], so that failures
+/// are recorded as [Event::AllocFailed], [Event::AllocZeroedFailed] or
+/// [Event::ReallocFailed] in the snapshot. Once the threshold is reached,
+/// requests fail without calling the underlying allocator.
+///
+/// Only allocations performed while [unmuted] are counted and failed, so
+/// allocations internal to checkers are never affected.
+///
+/// [Allocator]: crate::Allocator
+/// [Event::AllocFailed]: crate::Event::AllocFailed
+/// [Event::AllocZeroedFailed]: crate::Event::AllocZeroedFailed
+/// [Event::ReallocFailed]: crate::Event::ReallocFailed
+/// [unmuted]: crate::is_muted
+///
+/// # Examples
+///
+/// ```rust
+/// use checkers::{Allocator, FailingAllocator};
+/// use std::alloc::System;
+///
+/// #[global_allocator]
+/// static ALLOCATOR: Allocator<FailingAllocator> = Allocator::new(FailingAllocator::new(System, 1));
+///
+/// let snapshot = checkers::with(|| {
+///     let mut a = Vec::<u8>::new();
+///     assert!(a.try_reserve_exact(10).is_ok());
+///
+///     let mut b = Vec::<u8>::new();
+///     assert!(b.try_reserve_exact(10).is_err());
+/// });
+///
+/// assert_eq!(3, snapshot.events.len());
+/// assert!(snapshot.events[0].is_alloc_with(|r| r.size == 10));
+/// assert!(snapshot.events[1].is_failed());
+/// assert!(snapshot.events[2].is_free_with(|r| r.size == 10));
+/// ```
+pub struct FailingAllocator<T = std::alloc::System> {
+    delegate: T,
+    /// The number of allocations remaining before failures are injected.
+    remaining: AtomicUsize,
+}
+
+impl<T> FailingAllocator<T> {
+    /// Construct an allocator which permits `n` successful allocations through
+    /// `delegate`, after which every allocation fails.
+    pub const fn new(delegate: T, n: usize) -> Self {
+        Self {
+            delegate,
+            remaining: AtomicUsize::new(n),
+        }
+    }
+
+    /// Reset the allocator to permit `n` more successful allocations.
+    pub fn reset(&self, n: usize) {
+        self.remaining.store(n, Ordering::SeqCst);
+    }
+
+    /// Test if the next allocation should fail, counting it as performed if
+    /// not.
+    fn should_fail(&self) -> bool {
+        if crate::is_muted() {
+            return false;
+        }
+
+        self.remaining
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_err()
+    }
+}
+
+unsafe impl<T> GlobalAlloc for FailingAllocator<T>
+where
+    T: GlobalAlloc,
+{
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if self.should_fail() {
+            return ptr::null_mut();
+        }
+
+        self.delegate.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.delegate.dealloc(ptr, layout);
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        if self.should_fail() {
+            return ptr::null_mut();
+        }
+
+        self.delegate.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if self.should_fail() {
+            return ptr::null_mut();
+        }
+
+        self.delegate.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Seed used in place of zero, which is a fixed point of xorshift.
+const ZERO_SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Allocator which makes allocations fail at random, with a configured
+/// probability.
+///
+/// Failures are decided by a small deterministic pseudo-random number
+/// generator, so the same seed always reproduces the same pattern of
+/// failures for the same sequence of allocations.
+///
+/// Every injected failure is recorded as [Event::InjectedFailure] with the
+/// region that would have been allocated, using a null pointer. When used as
+/// the delegate of [Allocator] it is followed by the regular
+/// [Event::AllocFailed] or [Event::AllocZeroedFailed]. Reallocations are
+/// never failed.
+///
+/// Only allocations performed while [unmuted] are counted and failed, so
+/// allocations internal to checkers are never affected.
+///
+/// [Allocator]: crate::Allocator
+/// [unmuted]: crate::is_muted
+///
+/// # Examples
+///
+/// ```rust
+/// use checkers::{Allocator, Event, RandomFailingAllocator};
+/// use std::alloc::System;
+///
+/// #[global_allocator]
+/// static ALLOCATOR: Allocator<RandomFailingAllocator> =
+///     Allocator::new(RandomFailingAllocator::new(System, 42, 0.5));
+///
+/// let run = || {
+///     let mut results = Vec::with_capacity(16);
+///
+///     let snapshot = checkers::with(|| {
+///         for _ in 0..16 {
+///             let mut v = Vec::<u8>::new();
+///             results.push(v.try_reserve_exact(10).is_ok());
+///         }
+///     });
+///
+///     (results, snapshot)
+/// };
+///
+/// ALLOCATOR.delegate().reset(42);
+/// let (a, snapshot) = run();
+/// ALLOCATOR.delegate().reset(42);
+/// let (b, _) = run();
+///
+/// assert_eq!(a, b);
+///
+/// let injected = snapshot
+///     .events
+///     .iter()
+///     .filter(|e| matches!(e, Event::InjectedFailure(r) if r.size == 10))
+///     .count();
+///
+/// assert_eq!(a.iter().filter(|ok| !**ok).count(), injected);
+/// ```
+pub struct RandomFailingAllocator<T = std::alloc::System> {
+    delegate: T,
+    /// Allocations fail if the next random number is at most this.
+    threshold: u64,
+    /// The current state of the random number generator.
+    state: AtomicU64,
+}
+
+impl<T> RandomFailingAllocator<T> {
+    /// Construct an allocator which fails allocations through `delegate` with
+    /// the given `probability`, which is clamped to the range `0.0` to `1.0`.
+    /// The pattern of failures is decided by `seed`.
+    pub const fn new(delegate: T, seed: u64, probability: f64) -> Self {
+        // Note: float to int casts saturate, so this clamps the probability.
+        let threshold = (probability * 18_446_744_073_709_551_616.0) as u64;
+
+        Self {
+            delegate,
+            threshold,
+            state: AtomicU64::new(if seed == 0 { ZERO_SEED } else { seed }),
+        }
+    }
+
+    /// Reset the allocator to reproduce the pattern of failures decided by
+    /// `seed`.
+    pub fn reset(&self, seed: u64) {
+        let seed = if seed == 0 { ZERO_SEED } else { seed };
+        self.state.store(seed, Ordering::SeqCst);
+    }
+
+    /// Test if an allocation with the given layout should fail, recording
+    /// the injected failure if so.
+    fn should_fail(&self, layout: Layout) -> bool {
+        if crate::is_muted() {
+            return false;
+        }
+
+        let step = |mut x: u64| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x
+        };
+
+        let previous = self
+            .state
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |x| Some(step(x)))
+            .unwrap_or_else(|x| x);
+
+        // Note: xorshift never produces zero, so a zero threshold never fails.
+        if step(previous) > self.threshold {
+            return false;
+        }
+
+        let event = Event::InjectedFailure(Region::from_layout(Pointer::from(0), layout));
+        crate::with_recording_state(|s| s.events.push(event));
+        true
+    }
+}
+
+unsafe impl<T> GlobalAlloc for RandomFailingAllocator<T>
+where
+    T: GlobalAlloc,
+{
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if self.should_fail(layout) {
+            return ptr::null_mut();
+        }
+
+        self.delegate.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.delegate.dealloc(ptr, layout);
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        if self.should_fail(layout) {
+            return ptr::null_mut();
+        }
+
+        self.delegate.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        self.delegate.realloc(ptr, layout, new_size)
+    }
+}
@@ -0,0 +1,71 @@
+//! JSON export of snapshots.
+//!
+//! See [Snapshot::to_json] for a description of the schema.
+
+use serde::Serialize;
+
+use crate::{AllocZeroed, Event, Realloc, Region, Snapshot};
+
+/// The serialized form of a snapshot.
+#[derive(Serialize)]
+pub(crate) struct JsonSnapshot {
+    allocs: usize,
+    reallocs: usize,
+    frees: usize,
+    events: Vec<JsonEvent>,
+}
+
+impl JsonSnapshot {
+    pub(crate) fn new(snapshot: &Snapshot) -> Self {
+        let events = snapshot
+            .events
+            .iter()
+            .enumerate()
+            .map(|(index, event)| JsonEvent::new(index, *event))
+            .collect();
+
+        Self {
+            allocs: snapshot.events.allocs(),
+            reallocs: snapshot.events.reallocs(),
+            frees: snapshot.events.frees(),
+            events,
+        }
+    }
+}
+
+/// The serialized form of a single event.
+#[derive(Serialize)]
+struct JsonEvent {
+    index: usize,
+    kind: &'static str,
+    region: Option<Region>,
+    free: Option<Region>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ptr: Option<usize>,
+}
+
+impl JsonEvent {
+    fn new(index: usize, event: Event) -> Self {
+        let (region, free) = match event {
+            Event::Alloc(region) | Event::Free(region) | Event::InjectedFailure(region) => {
+                (Some(region), None)
+            }
+            Event::AllocZeroed(AllocZeroed { alloc, .. }) => (Some(alloc), None),
+            Event::Realloc(Realloc { free, alloc, .. }) => (Some(alloc), Some(free)),
+            _ => (None, None),
+        };
+
+        let ptr = match event {
+            Event::FreeByPtr(ptr) => Some(ptr.0),
+            _ => None,
+        };
+
+        Self {
+            index,
+            kind: event.kind(),
+            region,
+            free,
+            ptr,
+        }
+    }
+}
@@ -12,6 +12,7 @@
 //! * Freeing regions which are not allocated.
 //! * Freeing only part of regions which are allocated.
 //! * Freeing a region with a [mismatched layout].
+//! * Zero-sized allocations, which are not permitted by the allocator API.
 //! * That the underlying allocator produces regions adhering to the requested
 //!   layout. Namely size and alignment.
 //! * Detailed information on memory usage.
@@ -41,6 +42,11 @@
 //!
 //! The following are features available, that changes how checkers work.
 //!
+//! * `std` - Enables the [`Allocator`], [`with`] and everything else which
+//!   records allocations, since they rely on thread-local state. Without it
+//!   the crate is `no_std` and only requires `alloc`, which still permits
+//!   replaying histories through [`Machine`]. Enabled by default, and implied
+//!   by every feature which affects the allocator.
 //! * `realloc` - Enabling this feature causes checker to verify that a [realloc]
 //!   operation is correctly implemented. That bytes from the old region were
 //!   faithfully transferred to the new, resized one.
@@ -55,10 +61,32 @@
 //!   undefined behavior ([#1]) since it could read uninitialized memory.
 //! * `macros` - Enables dependencies and re-exports of macros, like
 //!   [`#[checkers::test]`](attr.test.html).
+//! * `serde` - Implements `Serialize` and `Deserialize` for [`Event`], [`Region`]
+//!   and [`Violation`] (and the types they contain), so that allocation
+//!   histories can be stored and replayed elsewhere. Pointers are serialized
+//!   as their numeric address.
+//! * `json` - Enables [`Snapshot::to_json`] and [`Snapshot::to_writer`], which
+//!   export a snapshot as JSON for use in external tooling. Implies `serde`.
+//! * `backtrace` - Enables [`Allocator::with_backtraces`], which captures a
+//!   backtrace at each allocation site. Capturing backtraces is expensive.
+//! * `proptest` - Enables [`event_strategy`], a [proptest] strategy which
+//!   generates valid allocation histories for property testing.
+//! * `arbitrary` - Implements [arbitrary]'s `Arbitrary` for [`Event`],
+//!   [`Region`] and [`Pointer`] (and the types they contain), so that events
+//!   can be generated by fuzzing harnesses. Generated alignments are always a
+//!   power of two.
+//! * `tracing` - Enables [`Allocator::with_tracing`], which emits
+//!   allocations as [tracing] events with the target `checkers::alloc`.
+//! * `timestamps` - Records the time of each event relative to the start of
+//!   the snapshot, see [`Events::timestamp`]. Reading the clock for every
+//!   allocation has a measurable overhead.
 //!
 //! [realloc]: std::alloc::GlobalAlloc::realloc
 //! [alloc_zeroed]: std::alloc::GlobalAlloc::alloc_zeroed
 //! [#1]: https://github.com/udoprog/checkers/issues/1
+//! [proptest]: https://docs.rs/proptest
+//! [arbitrary]: https://docs.rs/arbitrary
+//! [tracing]: https://docs.rs/tracing
 //!
 //! # Examples
 //!
@@ -115,37 +143,199 @@
 //! ```
 
 #![deny(missing_docs)]
+#![allow(clippy::test_attr_in_doctest)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
-use std::{
-    cell::{Cell, RefCell},
-    fmt,
+extern crate alloc;
+
+#[cfg(feature = "std")]
+use alloc::collections::BTreeMap;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::cell::{Cell, RefCell};
+#[cfg(feature = "std")]
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, Mutex, PoisonError,
 };
+#[cfg(feature = "timestamps")]
+use std::time::Instant;
 
+#[cfg(feature = "std")]
 mod allocator;
+#[cfg(feature = "std")]
+mod binary;
+#[cfg(feature = "std")]
+mod counting;
+#[cfg(feature = "std")]
+mod csv;
+#[cfg(feature = "std")]
+mod diff;
 mod event;
 mod events;
+#[cfg(feature = "std")]
+mod failing;
+#[cfg(feature = "json")]
+mod json;
 mod machine;
+#[cfg(feature = "std")]
+mod session;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "proptest")]
+mod strategy;
 mod utils;
 mod violation;
 
-pub use self::allocator::Allocator;
+#[cfg(feature = "std")]
+pub use self::allocator::{Allocator, AllocatorBuilder};
+#[cfg(feature = "std")]
+pub use self::binary::DecodeError;
+#[cfg(feature = "std")]
+pub use self::counting::CountingAllocator;
+#[cfg(feature = "std")]
+pub use self::diff::SnapshotDiff;
 pub use self::event::Event;
 pub use self::events::Events;
-pub use self::machine::{Machine, Region};
-pub use self::violation::Violation;
+#[cfg(feature = "std")]
+pub use self::failing::{FailingAllocator, RandomFailingAllocator};
+pub use self::machine::{Fragmentation, FreeOrder, Machine, MachineState, Region, RegionError};
+#[cfg(feature = "std")]
+pub use self::session::{DropPolicy, Session};
+#[cfg(feature = "std")]
+pub use self::stats::Stats;
+#[cfg(feature = "proptest")]
+pub use self::strategy::event_strategy;
+pub use self::violation::{Severity, Violation, ViolationCode};
+#[cfg(feature = "backtrace")]
+pub use backtrace::Backtrace;
 #[cfg(feature = "macros")]
 pub use checkers_macros::test;
 
+#[cfg(feature = "std")]
 thread_local! {
     /// Thread-local state required by the allocator.
     ///
     /// Feel free to interact with this directly, but it's primarily used
     /// through the [`test`](crate::test) macro.
-    static STATE: RefCell<State> = RefCell::new(State::new());
-    static MUTED: Cell<bool> = Cell::new(true);
+    static STATE: RefCell<State> = const { RefCell::new(State::new()) };
+    static MUTED: Cell<Option<bool>> = const { Cell::new(None) };
+    /// The shard the current thread records into while [with_threads] is
+    /// running, together with the generation of the run it belongs to.
+    static SHARD: RefCell<Option<(usize, Arc<Mutex<Shard>>)>> = const { RefCell::new(None) };
+    /// Set while the current thread holds the lock on its [SHARD].
+    static HOLDING_SHARD: Cell<bool> = const { Cell::new(false) };
+    /// The label events are currently tagged with, see [with_tag].
+    static TAG: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
+/// The shards of every thread which has recorded events while [with_threads]
+/// is running.
+#[cfg(feature = "std")]
+static SHARDS: Mutex<Shards> = Mutex::new(Shards::new());
+
+/// Indicates if [with_threads] is currently running.
+#[cfg(feature = "std")]
+static SHARED_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// The generation of the current run of [with_threads], so that threads can
+/// tell if their cached shard belongs to an earlier run.
+#[cfg(feature = "std")]
+static SHARED_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// The number of events which have been recorded by all threads in the
+/// current run of [with_threads], used to order events when merging shards.
+#[cfg(feature = "std")]
+static SHARED_SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+
+/// The number of bytes currently live according to the allocations of all
+/// threads in the current run of [with_threads].
+#[cfg(feature = "std")]
+static SHARED_MEMORY_USED: AtomicUsize = AtomicUsize::new(0);
+
+/// The highest value [SHARED_MEMORY_USED] has reached.
+#[cfg(feature = "std")]
+static SHARED_PEAK_MEMORY: AtomicUsize = AtomicUsize::new(0);
+
+/// The buffer a single thread records into while [with_threads] is running.
+#[cfg(feature = "std")]
+#[derive(Default)]
+struct Shard {
+    /// The state recorded by the thread.
+    state: State,
+    /// The position of each event in `state` among the events recorded by all
+    /// threads.
+    order: Vec<usize>,
+}
+
+#[cfg(feature = "std")]
+impl Shard {
+    /// Record into the shard, keeping the memory used by all threads up to
+    /// date and assigning a position to every event recorded.
+    fn record<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut State) -> R,
+    {
+        let len = self.state.events.len();
+        let used = SHARED_MEMORY_USED.load(Ordering::Relaxed);
+        self.state.memory_used = used;
+
+        let result = f(&mut self.state);
+
+        if self.state.memory_used != used {
+            let delta = self.state.memory_used.abs_diff(used);
+            let grew = self.state.memory_used > used;
+
+            let previous = SHARED_MEMORY_USED
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                    Some(if grew {
+                        n.saturating_add(delta)
+                    } else {
+                        n.saturating_sub(delta)
+                    })
+                })
+                .unwrap_or_else(|n| n);
+
+            if grew {
+                SHARED_PEAK_MEMORY.fetch_max(previous.saturating_add(delta), Ordering::Relaxed);
+            }
+        }
+
+        let recorded = self.state.events.len() - len;
+
+        if recorded > 0 {
+            let _g = crate::mute_guard(true);
+            let first = SHARED_SEQUENCE.fetch_add(recorded, Ordering::Relaxed);
+            self.order.extend(first..first + recorded);
+        }
+
+        result
+    }
+}
+
+/// The shards registered for the current run of [with_threads].
+#[cfg(feature = "std")]
+struct Shards {
+    /// The instant that timestamps of the run are relative to.
+    #[cfg(feature = "timestamps")]
+    start: Option<Instant>,
+    /// The shard of every thread which has recorded into the run.
+    buffers: Vec<Arc<Mutex<Shard>>>,
+}
+
+#[cfg(feature = "std")]
+impl Shards {
+    const fn new() -> Self {
+        Self {
+            #[cfg(feature = "timestamps")]
+            start: None,
+            buffers: Vec::new(),
+        }
+    }
 }
 
 /// Perform an operation, while having access to the thread-local state.
+#[cfg(feature = "std")]
 pub fn with_state<F, R>(f: F) -> R
 where
     F: FnOnce(&RefCell<State>) -> R,
@@ -153,7 +343,129 @@ where
     crate::STATE.with(f)
 }
 
-/// Test if the crate is currently muted. The allocator is muted by default.
+/// Perform an operation on the state which the allocator is currently
+/// recording into. This is the shard of the current thread while
+/// [with_threads] is running, and the thread-local state otherwise.
+#[cfg(feature = "std")]
+pub(crate) fn with_recording_state<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut State) -> R,
+{
+    // Note: an allocation which is recorded while we already hold the lock on
+    // the shard can't wait for it, so it falls back to being recorded
+    // locally. So does one recorded while the thread is being torn down.
+    if is_sharing() {
+        let _holding = HoldingGuard::new();
+        let mut f = Some(f);
+
+        let result = SHARD.try_with(|cell| {
+            let mut cell = cell.borrow_mut();
+            let generation = SHARED_GENERATION.load(Ordering::Acquire);
+
+            if !matches!(&*cell, Some((g, _)) if *g == generation) {
+                // Note: replacing the shard of an earlier run might free it.
+                let _g = crate::mute_guard(true);
+                *cell = Some((generation, register_shard()));
+            }
+
+            let (_, shard) = cell.as_ref().expect("shard was just registered");
+            let mut shard = shard.lock().unwrap_or_else(PoisonError::into_inner);
+            let f = f.take().expect("closure is only called once");
+            shard.record(f)
+        });
+
+        if let Ok(result) = result {
+            return result;
+        }
+
+        let f = f.take().expect("closure was not called");
+        return record_locally(f);
+    }
+
+    record_locally(f)
+}
+
+/// Record into the thread-local state.
+#[cfg(feature = "std")]
+fn record_locally<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut State) -> R,
+{
+    let mut f = Some(f);
+
+    let result = crate::STATE.try_with(|s| {
+        let f = f.take().expect("closure is only called once");
+        f(&mut s.borrow_mut())
+    });
+
+    if let Ok(result) = result {
+        return result;
+    }
+
+    // Note: the thread-local state is gone while the thread is being torn
+    // down, in which case the event is discarded.
+    let _g = crate::mute_guard(true);
+    let mut state = State::new();
+    let f = f.take().expect("closure was not called");
+    f(&mut state)
+}
+
+/// Test if the current thread records into a shard of the running
+/// [with_threads], rather than into its thread-local state.
+///
+/// A thread which is inside of [with] or a [Session] keeps recording into its
+/// thread-local state, so that the snapshot it's capturing stays complete.
+#[cfg(feature = "std")]
+pub(crate) fn is_sharing() -> bool {
+    if !SHARED_ACTIVE.load(Ordering::Acquire) || HOLDING_SHARD.with(Cell::get) {
+        return false;
+    }
+
+    !matches!(STATE.try_with(|s| s.try_borrow().map(|s| s.depth)), Ok(Ok(depth)) if depth > 0)
+}
+
+/// Register a new shard for the current thread in the current run of
+/// [with_threads].
+#[cfg(feature = "std")]
+fn register_shard() -> Arc<Mutex<Shard>> {
+    let mut shards = SHARDS.lock().unwrap_or_else(PoisonError::into_inner);
+
+    #[cfg_attr(not(feature = "timestamps"), allow(unused_mut))]
+    let mut shard = Shard::default();
+
+    #[cfg(feature = "timestamps")]
+    if let Some(start) = shards.start {
+        shard.state.events.start_clock_at(start);
+    }
+
+    let shard = Arc::new(Mutex::new(shard));
+    shards.buffers.push(shard.clone());
+    shard
+}
+
+/// Guard which marks the current thread as holding the lock on its shard, and
+/// clears the mark even if we are unwinding due to a panic.
+#[cfg(feature = "std")]
+struct HoldingGuard;
+
+#[cfg(feature = "std")]
+impl HoldingGuard {
+    fn new() -> Self {
+        HOLDING_SHARD.with(|h| h.set(true));
+        Self
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for HoldingGuard {
+    fn drop(&mut self) {
+        HOLDING_SHARD.with(|h| h.set(false));
+    }
+}
+
+/// Test if the crate is currently muted. The allocator is muted by default,
+/// except while [with_threads] is running, where threads which haven't been
+/// explicitly muted or unmuted are recorded.
 ///
 /// We mute the allocator for allocations we don't want to be tracked. This is
 /// useful to avoid tracing internal allocations.
@@ -184,8 +496,11 @@ where
 /// assert!(result.is_err());
 /// assert!(checkers::is_muted());
 /// ```
+#[cfg(feature = "std")]
 pub fn is_muted() -> bool {
-    MUTED.with(Cell::get)
+    MUTED
+        .with(Cell::get)
+        .unwrap_or_else(|| !SHARED_ACTIVE.load(Ordering::Acquire))
 }
 
 /// Enable muting for the duration of the guard. A guard ensures that the muted
@@ -193,13 +508,15 @@ pub fn is_muted() -> bool {
 /// panic. You should prefer to use [with_unmuted] when possible.
 ///
 /// See [is_muted] for details on what this means.
+#[cfg(feature = "std")]
 pub fn mute_guard(muted: bool) -> MuteGuard {
-    MuteGuard(MUTED.with(|s| s.replace(muted)))
+    MuteGuard(MUTED.with(|s| s.replace(Some(muted))))
 }
 
 /// Run the given closure while the allocator is unmuted.
 ///
 /// See [is_muted] for details on what this means.
+#[cfg(feature = "std")]
 pub fn with_unmuted<F, R>(f: F) -> R
 where
     F: FnOnce() -> R,
@@ -231,6 +548,36 @@ where
 /// // allocation from analysis.
 /// checkers::verify!(snapshot);
 /// ```
+///
+/// Allocations performed while muted are still passed on to the delegate
+/// allocator, they are just not recorded. Since the previous state is restored
+/// once the closure returns, even if it panics, this can be nested and used
+/// to pause recording during a noisy phase inside of [with]. Regions allocated
+/// while muted also need to be freed while muted, since their frees would
+/// otherwise be recorded without a matching allocation.
+///
+/// ```rust
+/// #[global_allocator]
+/// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+///
+/// let snapshot = checkers::with(|| {
+///     checkers::with_muted(|| {
+///         let _ = vec![1, 2, 3, 4];
+///
+///         checkers::with_muted(|| {
+///             let _ = vec![1, 2, 3, 4];
+///         });
+///
+///         let _ = vec![1, 2, 3, 4];
+///     });
+///
+///     let _ = Box::new(42);
+/// });
+///
+/// assert_eq!(1, snapshot.events.allocs());
+/// assert_eq!(1, snapshot.events.frees());
+/// ```
+#[cfg(feature = "std")]
 pub fn with_muted<F, R>(f: F) -> R
 where
     F: FnOnce() -> R,
@@ -240,14 +587,77 @@ where
 }
 
 /// A helper guard to make sure the state is de-allocated on drop.
-pub struct MuteGuard(bool);
+#[cfg(feature = "std")]
+pub struct MuteGuard(Option<bool>);
 
+#[cfg(feature = "std")]
 impl Drop for MuteGuard {
     fn drop(&mut self) {
         MUTED.with(|s| s.set(self.0));
     }
 }
 
+/// Run the given closure while tagging every event recorded by the current
+/// thread with `label`, see [Events::tag].
+///
+/// Tags nest, so an inner tag overrides the outer one for the duration of its
+/// closure. The previous tag is restored once the closure returns, even if it
+/// panics. Tags don't carry over to threads spawned in the closure, and are
+/// ignored by [Machine] when validating events.
+///
+/// # Examples
+///
+/// ```rust
+/// #[global_allocator]
+/// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+///
+/// let snapshot = checkers::with(|| {
+///     checkers::with_tag("parser", || {
+///         let _ = Box::new(1);
+///
+///         checkers::with_tag("lexer", || {
+///             let _ = Box::new(2);
+///         });
+///     });
+///
+///     let _ = Box::new(3);
+/// });
+///
+/// assert_eq!(Some("parser"), snapshot.events.tag(0));
+/// assert_eq!(Some("lexer"), snapshot.events.tag(2));
+/// assert_eq!(None, snapshot.events.tag(4));
+///
+/// let groups = snapshot.group_by_tag();
+/// assert_eq!(2, groups["parser"].len());
+/// assert_eq!(2, groups["lexer"].len());
+/// assert_eq!(2, groups.len());
+/// ```
+#[cfg(feature = "std")]
+pub fn with_tag<F, R>(label: &'static str, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let _g = TagGuard(TAG.with(|t| t.replace(Some(label))));
+    f()
+}
+
+/// Get the label which events recorded by the current thread are tagged with.
+#[cfg(feature = "std")]
+pub(crate) fn current_tag() -> Option<&'static str> {
+    TAG.with(Cell::get)
+}
+
+/// Guard which restores the previous tag on drop.
+#[cfg(feature = "std")]
+struct TagGuard(Option<&'static str>);
+
+#[cfg(feature = "std")]
+impl Drop for TagGuard {
+    fn drop(&mut self) {
+        TAG.with(|t| t.set(self.0));
+    }
+}
+
 /// Verify the state of the allocator.
 ///
 /// Note: this macro is used by default if the `verify` parameter is not
@@ -275,29 +685,51 @@ impl Drop for MuteGuard {
 ///     let _ = Box::into_raw(vec![1, 2, 3, 4, 5].into_boxed_slice());
 /// }
 /// ```
+#[cfg(feature = "std")]
 #[macro_export]
 macro_rules! verify {
     ($state:expr) => {
         let mut validations = Vec::new();
         $state.validate(&mut validations);
 
-        for e in &validations {
-            eprintln!("{}", e);
-        }
-
         if !validations.is_empty() {
-            panic!("allocation checks failed");
+            let mut report = String::new();
+
+            for e in &validations {
+                report.push_str(&format!("\n  {}", e));
+            }
+
+            panic!("allocation checks failed:{}", report);
         }
     };
 }
 
 /// A snapshot of the state of the checkers allocator.
+#[cfg(feature = "std")]
 #[derive(Debug)]
 pub struct Snapshot {
     /// Snapshot of all collected events.
     pub events: Events,
+    /// Backtraces captured for events, indexed by the position of the event
+    /// in [Snapshot::events].
+    ///
+    /// See [Allocator::with_backtraces].
+    #[cfg(feature = "backtrace")]
+    pub backtraces: BTreeMap<usize, Backtrace>,
+    /// The highest number of bytes live at once while the snapshot was taken.
+    pub peak_memory: usize,
+    /// The memory budget enforced by the allocator, if any.
+    ///
+    /// See [Allocator::with_max_bytes].
+    pub max_bytes: Option<usize>,
+    /// The number of allocations which were not recorded since they were
+    /// smaller than the minimum tracked size.
+    ///
+    /// See [Allocator::with_min_tracked_size].
+    pub skipped_allocs: usize,
 }
 
+#[cfg(feature = "std")]
 impl Snapshot {
     /// Validate the current snapshot.
     ///
@@ -305,6 +737,429 @@ impl Snapshot {
     pub fn validate(&self, errors: &mut Vec<Violation>) {
         self.events.validate(errors);
     }
+
+    /// Assert that no regions were leaked in the snapshot.
+    ///
+    /// # Panics
+    ///
+    /// Panics with a report listing every leaked region if any regions are
+    /// still live at the end of the snapshot. Other violations are ignored,
+    /// use [verify!] to check for them as well.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #[global_allocator]
+    /// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+    ///
+    /// let snapshot = checkers::with(|| {
+    ///     let _ = Box::new(1);
+    /// });
+    ///
+    /// snapshot.assert_no_leaks();
+    ///
+    /// let snapshot = checkers::with(|| {
+    ///     let _ = Box::into_raw(vec![0u8; 2048].into_boxed_slice());
+    /// });
+    ///
+    /// let result = std::panic::catch_unwind(|| snapshot.assert_no_leaks());
+    /// let message = *result.unwrap_err().downcast::<String>().unwrap();
+    /// assert!(message.starts_with("1 region leaked (2.00 KiB):\n  0x"));
+    /// ```
+    pub fn assert_no_leaks(&self) {
+        let mut machine = Machine::default();
+        let _ = machine.push_all(self.events.iter().copied());
+
+        if machine.is_clean() {
+            return;
+        }
+
+        let leaks = machine.trailing_regions();
+        let mut report = String::new();
+
+        for region in &leaks {
+            report.push_str(&format!("\n  {}", region));
+        }
+
+        panic!(
+            "{} {} leaked ({}):{}",
+            leaks.len(),
+            if leaks.len() == 1 {
+                "region"
+            } else {
+                "regions"
+            },
+            utils::Bytes(machine.memory_used()),
+            report
+        );
+    }
+
+    /// Compute the difference from this snapshot to `other`.
+    ///
+    /// This is useful to compare the same operation across two versions of
+    /// the code, to see which allocation patterns changed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #[global_allocator]
+    /// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+    ///
+    /// let before = checkers::with(|| {
+    ///     let _ = vec![0u8; 1024];
+    /// });
+    ///
+    /// let after = checkers::with(|| {
+    ///     for _ in 0..50 {
+    ///         let _ = vec![0u8; 1024];
+    ///     }
+    /// });
+    ///
+    /// let diff = before.diff(&after);
+    /// assert_eq!(49, diff.allocs);
+    /// assert_eq!(49, diff.frees);
+    /// assert_eq!(0, diff.peak_memory);
+    /// assert_eq!(49 * 1024, diff.total_allocated);
+    /// assert_eq!(Some(&49), diff.size_buckets.get(&1024));
+    /// ```
+    pub fn diff(&self, other: &Snapshot) -> SnapshotDiff {
+        SnapshotDiff::new(self, other)
+    }
+
+    /// Collect summary statistics of the snapshot, so that they can be
+    /// compared in a single assertion.
+    ///
+    /// Current memory and leaks are found by replaying the events through a
+    /// [Machine], like [assert_no_leaks][Snapshot::assert_no_leaks] does.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::Stats;
+    ///
+    /// #[global_allocator]
+    /// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+    ///
+    /// let snapshot = checkers::with(|| {
+    ///     let mut v = Vec::<u8>::with_capacity(16);
+    ///     v.reserve_exact(32);
+    ///     std::mem::forget(v);
+    ///     let _ = Box::new(0u64);
+    /// });
+    ///
+    /// assert_eq!(
+    ///     Stats {
+    ///         allocs: 2,
+    ///         frees: 1,
+    ///         reallocs: 1,
+    ///         peak_memory: 40,
+    ///         current_memory: 32,
+    ///         total_allocated: 40,
+    ///         leak_count: 1,
+    ///         leak_bytes: 32,
+    ///     },
+    ///     snapshot.stats()
+    /// );
+    /// ```
+    pub fn stats(&self) -> Stats {
+        Stats::new(self)
+    }
+
+    /// Move every pointer recorded in the snapshot from being relative to
+    /// `old_base` to being relative to `new_base`, see [Region::rebase].
+    ///
+    /// This can be used to normalize snapshots of runs which were placed at
+    /// different addresses, for example due to address space layout
+    /// randomization, before comparing them.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event, Region};
+    ///
+    /// #[global_allocator]
+    /// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+    ///
+    /// let mut snapshot = checkers::with(|| {
+    ///     let _ = Box::new(1u32);
+    /// });
+    ///
+    /// let base = match snapshot.events[0] {
+    ///     Event::Alloc(region) => region.ptr,
+    ///     _ => panic!("expected an allocation"),
+    /// };
+    ///
+    /// snapshot.rebase(base, 0x1000.into());
+    /// assert_eq!(Event::Alloc(Region::new(0x1000.into(), 4, 4)), snapshot.events[0]);
+    /// assert_eq!(Event::Free(Region::new(0x1000.into(), 4, 4)), snapshot.events[1]);
+    /// ```
+    pub fn rebase(&mut self, old_base: Pointer, new_base: Pointer) {
+        for event in self.events.as_slice_mut() {
+            *event = event.rebase(old_base, new_base);
+        }
+    }
+
+    /// Get the lifetime of every region freed while the snapshot was taken,
+    /// measured in events.
+    ///
+    /// See [Events::lifetimes] for more documentation, and
+    /// [Events::live_lifetimes] for regions which are still live.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #[global_allocator]
+    /// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+    ///
+    /// let snapshot = checkers::with(|| {
+    ///     let a = Box::new(1);
+    ///     let _ = Box::new(2);
+    ///     drop(a);
+    /// });
+    ///
+    /// let lifetimes = snapshot.lifetimes();
+    /// assert_eq!(vec![1, 3], lifetimes.iter().map(|(_, n)| *n).collect::<Vec<_>>());
+    /// ```
+    pub fn lifetimes(&self) -> Vec<(Region, usize)> {
+        self.events.lifetimes()
+    }
+
+    /// Group the tagged events of the snapshot by their tag, together with
+    /// their index. Untagged events are left out.
+    ///
+    /// See [with_tag] for how to tag events.
+    pub fn group_by_tag(&self) -> BTreeMap<&'static str, Vec<(usize, Event)>> {
+        let mut groups = BTreeMap::<_, Vec<_>>::new();
+
+        for (index, event) in self.events.iter().enumerate() {
+            if let Some(tag) = self.events.tag(index) {
+                groups.entry(tag).or_default().push((index, *event));
+            }
+        }
+
+        groups
+    }
+
+    /// Access the backtrace captured when the given region was most recently
+    /// allocated, either through an allocation or a reallocation.
+    ///
+    /// Backtraces are only captured if enabled through
+    /// [Allocator::with_backtraces]. Captured backtraces are unresolved, so
+    /// call [Backtrace::resolve] on a clone to get symbol names.
+    #[cfg(feature = "backtrace")]
+    pub fn alloc_backtrace(&self, region: Region) -> Option<&Backtrace> {
+        let (index, _) = self.events.iter().enumerate().rev().find(|(_, e)| {
+            e.is_alloc_with(|r| r == region) || e.is_realloc_with(|r| r.alloc == region)
+        })?;
+
+        self.backtraces.get(&index)
+    }
+
+    /// Serialize the snapshot as JSON.
+    ///
+    /// The produced document is an object with the following fields:
+    /// * `allocs` - The number of allocations, see [Events::allocs].
+    /// * `reallocs` - The number of reallocations, see [Events::reallocs].
+    /// * `frees` - The number of frees, see [Events::frees].
+    /// * `events` - An array of events in the order they were recorded.
+    ///
+    /// Each event is an object with the following fields:
+    /// * `index` - The sequence index of the event.
+    /// * `kind` - One of `alloc`, `free`, `free_by_ptr`, `alloc_zeroed`,
+    ///   `realloc`, `alloc_failed`, `alloc_zeroed_failed`, `realloc_null`,
+    ///   `realloc_failed`, `budget_exceeded` or `injected_failure`.
+    /// * `region` - The region allocated or freed by the event, or `null` for
+    ///   failed events and frees by pointer. For reallocations this is the new
+    ///   region, and for injected failures the region which would have been
+    ///   allocated.
+    /// * `free` - The region freed by a reallocation, or `null` for every other
+    ///   kind of event.
+    /// * `ptr` - The pointer freed by a `free_by_ptr` event. Only present for
+    ///   that kind of event.
+    ///
+    /// Regions are objects with the fields `ptr` (the numeric address),
+    /// `size` and `align`.
+    ///
+    /// Consumers should look fields up by name, since new fields might be
+    /// added in the future.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #[global_allocator]
+    /// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+    ///
+    /// let snapshot = checkers::with(|| {
+    ///     let _ = vec![1, 2, 3, 4];
+    /// });
+    ///
+    /// let json = snapshot.to_json();
+    /// assert!(json.contains(r#""kind":"alloc""#));
+    /// assert!(json.contains(r#""kind":"free""#));
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&json::JsonSnapshot::new(self))
+            .expect("serializing a snapshot cannot fail")
+    }
+
+    /// Serialize the snapshot as JSON into the given writer.
+    ///
+    /// See [Snapshot::to_json] for a description of the schema.
+    #[cfg(feature = "json")]
+    pub fn to_writer<W>(&self, writer: W) -> serde_json::Result<()>
+    where
+        W: std::io::Write,
+    {
+        serde_json::to_writer(writer, &json::JsonSnapshot::new(self))
+    }
+
+    /// Encode the snapshot in a compact binary format, which can be decoded
+    /// with [Snapshot::from_bytes].
+    ///
+    /// The encoding starts with a header consisting of the magic bytes
+    /// `CHKR`, a version byte, [peak_memory][Snapshot::peak_memory],
+    /// [max_bytes][Snapshot::max_bytes] as a presence byte followed by its
+    /// value, [skipped_allocs][Snapshot::skipped_allocs] and the number of
+    /// events. It's followed by one fixed-width record of 50 bytes per event,
+    /// consisting of a kind byte, a flag byte holding `is_zeroed` or
+    /// `is_relocated` if present, and two regions of pointer, size and
+    /// alignment. All integers are encoded as 64-bit little-endian.
+    ///
+    /// Backtraces are not encoded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #[global_allocator]
+    /// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+    ///
+    /// let snapshot = checkers::with(|| {
+    ///     let _ = vec![1, 2, 3, 4];
+    /// });
+    ///
+    /// let bytes = snapshot.to_bytes();
+    /// let decoded = checkers::Snapshot::from_bytes(&bytes).unwrap();
+    ///
+    /// assert_eq!(snapshot.events.as_slice(), decoded.events.as_slice());
+    /// assert_eq!(snapshot.peak_memory, decoded.peak_memory);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        binary::encode(self)
+    }
+
+    /// Decode a snapshot encoded with [Snapshot::to_bytes].
+    ///
+    /// Decoded events are recorded as if they were issued by the current
+    /// thread.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{DecodeError, Snapshot};
+    ///
+    /// assert_eq!(
+    ///     DecodeError::InvalidMagic,
+    ///     Snapshot::from_bytes(b"JSON").unwrap_err()
+    /// );
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Result<Snapshot, DecodeError> {
+        binary::decode(bytes)
+    }
+
+    /// Write the events of the snapshot as CSV into the given writer.
+    ///
+    /// The first row is the header `index,kind,ptr,size,align,memory_used`,
+    /// followed by one row per event:
+    /// * `index` - The sequence index of the event.
+    /// * `kind` - The kind of the event, named like in [Snapshot::to_json].
+    /// * `ptr`, `size` and `align` - The region allocated or freed by the
+    ///   event, with the pointer in hex. For reallocations this is the new
+    ///   region. Empty for failed events, and only `ptr` is set for frees by
+    ///   pointer.
+    /// * `memory_used` - The number of bytes live after the event.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #[global_allocator]
+    /// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+    ///
+    /// let snapshot = checkers::with(|| {
+    ///     let _ = Box::new(42u64);
+    /// });
+    ///
+    /// let mut csv = Vec::new();
+    /// snapshot.to_csv(&mut csv).unwrap();
+    /// let csv = String::from_utf8(csv).unwrap();
+    /// let rows = csv.lines().collect::<Vec<_>>();
+    ///
+    /// assert_eq!(3, rows.len());
+    /// assert_eq!("index,kind,ptr,size,align,memory_used", rows[0]);
+    /// assert!(rows[1].starts_with("0,alloc,0x"));
+    /// assert!(rows[1].ends_with(",8,8,8"));
+    /// assert!(rows[2].ends_with(",8,8,0"));
+    /// ```
+    pub fn to_csv<W>(&self, w: W) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        csv::write(self, w)
+    }
+}
+
+/// Format a report of what happened while the snapshot was taken.
+///
+/// The report contains the number of allocations, reallocations and frees,
+/// the peak and current memory use, and every region which was leaked.
+/// Current memory use and leaks are determined by replaying the events
+/// through a [Machine].
+///
+/// # Examples
+///
+/// ```rust
+/// #[global_allocator]
+/// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+///
+/// let snapshot = checkers::with(|| {
+///     let _ = vec![1u8; 16];
+///     let _ = Box::into_raw(Box::new([0u8; 32]));
+/// });
+///
+/// let report = snapshot.to_string();
+/// assert!(report.contains("allocs: 2, reallocs: 0, frees: 1"));
+/// assert!(report.contains("peak memory: 32 B, memory used: 32 B"));
+/// assert!(report.contains("leaked regions: 1"));
+/// ```
+#[cfg(feature = "std")]
+impl fmt::Display for Snapshot {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut machine = Machine::default();
+        let _ = machine.push_all(self.events.iter().copied());
+
+        writeln!(
+            fmt,
+            "allocs: {}, reallocs: {}, frees: {}",
+            self.events.allocs(),
+            self.events.reallocs(),
+            self.events.frees()
+        )?;
+        writeln!(
+            fmt,
+            "peak memory: {}, memory used: {}",
+            utils::Bytes(self.peak_memory),
+            utils::Bytes(machine.memory_used())
+        )?;
+
+        let leaks = machine.trailing_regions();
+        write!(fmt, "leaked regions: {}", leaks.len())?;
+
+        for region in leaks {
+            write!(fmt, "\n  {}", region)?;
+        }
+
+        Ok(())
+    }
 }
 
 /// Run the specified closure and return a snapshot of the memory state
@@ -330,35 +1185,310 @@ impl Snapshot {
 /// assert_eq!(1, snapshot.events.frees());
 /// assert!(snapshot.events.max_memory_used().unwrap() >= 16);
 /// ```
+///
+/// Calls to `with` can be nested. The inner snapshot only contains the events
+/// issued within its closure, while the outer snapshot contains all of them.
+/// The peak memory of an inner snapshot only counts memory allocated within
+/// its closure.
+///
+/// ```rust
+/// #[global_allocator]
+/// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+///
+/// let mut inner = None;
+///
+/// let outer = checkers::with(|| {
+///     let a = vec![1u8; 16];
+///
+///     inner = Some(checkers::with(|| {
+///         let _ = vec![1u8; 32];
+///     }));
+///
+///     drop(a);
+/// });
+///
+/// let inner = inner.unwrap();
+///
+/// assert_eq!(2, inner.events.len());
+/// assert_eq!(32, inner.peak_memory);
+///
+/// assert_eq!(4, outer.events.len());
+/// assert_eq!(48, outer.peak_memory);
+/// ```
+#[cfg(feature = "std")]
 pub fn with<F>(f: F) -> Snapshot
 where
     F: FnOnce(),
 {
-    crate::with_state(|s| {
-        s.borrow_mut().events.clear();
+    let session = Session::begin();
+    f();
+    session.finish()
+}
+
+/// Start capturing every allocation performed by the current thread, until
+/// the returned [Session] is dropped or [finished][Session::finish].
+///
+/// This behaves like [with] without the closure, and nests with it in the
+/// same way. What happens when the session is dropped without being finished
+/// is configured with [Session::with_drop_policy].
+///
+/// # Examples
+///
+/// ```rust
+/// #[global_allocator]
+/// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+///
+/// let session = checkers::begin();
+/// let _ = vec![1, 2, 3, 4];
+/// let snapshot = session.finish();
+///
+/// assert_eq!(2, snapshot.events.len());
+/// checkers::verify!(snapshot);
+/// ```
+///
+/// A session which is dropped early still leaves its scope, so it can guard
+/// the rest of a function:
+///
+/// ```rust
+/// use checkers::DropPolicy;
+///
+/// #[global_allocator]
+/// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+///
+/// fn work() {
+///     let _g = checkers::begin().with_drop_policy(DropPolicy::PanicOnLeak);
+///     let _ = vec![1, 2, 3, 4];
+/// }
+///
+/// work();
+/// ```
+#[cfg(feature = "std")]
+pub fn begin() -> Session {
+    Session::begin()
+}
+
+/// Run the given closure like [with], and validate every event it captured.
+///
+/// Returns the value produced by the closure if no violations were found, or
+/// every violation otherwise. Any region which is still live once the closure
+/// returns is reported as [Violation::Leaked], so this can be used with `?` in
+/// tests which don't permit any net allocations.
+///
+/// Note that a value returned by the closure which holds on to allocations is
+/// reported as leaked, since it outlives the scope.
+///
+/// # Examples
+///
+/// ```rust
+/// #[global_allocator]
+/// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+///
+/// let len = checkers::with_checked(|| vec![1, 2, 3, 4].len());
+/// assert_eq!(Ok(4), len);
+///
+/// let leaked = checkers::with_checked(|| std::mem::forget(vec![1, 2, 3, 4]));
+/// let violations = leaked.unwrap_err();
+/// assert_eq!(1, violations.len());
+/// assert!(violations[0].is_leaked_with(|r| r.size == 16));
+/// ```
+#[cfg(feature = "std")]
+pub fn with_checked<F, T>(f: F) -> Result<T, Vec<Violation>>
+where
+    F: FnOnce() -> T,
+{
+    let mut output = None;
+
+    let snapshot = crate::with(|| {
+        output = Some(f());
+    });
+
+    let _g = crate::mute_guard(true);
+    let mut violations = Vec::new();
+    snapshot.validate(&mut violations);
+
+    if !violations.is_empty() {
+        return Err(violations);
+    }
+
+    Ok(output.expect("closure must have returned"))
+}
+
+/// Run the given closure and capture a snapshot of all allocations performed
+/// by any thread while it runs.
+///
+/// Unlike [with], this also covers threads spawned within the closure. Each
+/// thread records into a buffer of its own, which are merged in the order
+/// events were recorded in once the closure returns. Every event keeps the id
+/// of the thread which issued it, see [Events::by_thread]. Threads need to
+/// finish before the closure returns to be fully captured. Note that this
+/// also captures threads which aren't related to the closure, like other
+/// tests running in parallel.
+///
+/// Mixing this with [with] or [begin] is not supported. A thread which is
+/// inside of either keeps recording into its own thread-local state, so its
+/// allocations are missing from the snapshot returned by `with_threads`. This
+/// includes the calling thread if `with_threads` runs inside of [with].
+///
+/// While running, reallocations on the recording threads are performed as an
+/// allocation followed by a free, so that they can be ordered correctly with
+/// respect to other threads.
+///
+/// # Panics
+///
+/// Panics if called while another call to `with_threads` is running.
+///
+/// # Examples
+///
+/// ```rust
+/// #[global_allocator]
+/// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+///
+/// let mut spawned = None;
+///
+/// let snapshot = checkers::with_threads(|| {
+///     let handle = std::thread::spawn(|| {
+///         let _ = vec![1u8; 64];
+///         std::thread::current().id()
+///     });
+///
+///     spawned = Some(handle.join().unwrap());
+/// });
+///
+/// let spawned = spawned.unwrap();
+/// assert!(snapshot
+///     .events
+///     .by_thread(spawned)
+///     .any(|(_, e)| e.is_alloc_with(|r| r.size == 64)));
+/// ```
+#[cfg(feature = "std")]
+pub fn with_threads<F>(f: F) -> Snapshot
+where
+    F: FnOnce(),
+{
+    {
+        let _g = crate::mute_guard(true);
+        let mut shards = SHARDS.lock().unwrap_or_else(PoisonError::into_inner);
+        assert!(
+            !SHARED_ACTIVE.load(Ordering::Acquire),
+            "with_threads can't be nested"
+        );
+        shards.buffers.clear();
+        #[cfg(feature = "timestamps")]
+        {
+            shards.start = Some(Instant::now());
+        }
+        SHARED_GENERATION.fetch_add(1, Ordering::AcqRel);
+        SHARED_SEQUENCE.store(0, Ordering::Relaxed);
+        SHARED_MEMORY_USED.store(0, Ordering::Relaxed);
+        SHARED_PEAK_MEMORY.store(0, Ordering::Relaxed);
+        SHARED_ACTIVE.store(true, Ordering::Release);
+    }
+
+    let guard = SharedGuard;
+    crate::with_unmuted(f);
+    drop(guard);
+
+    let _g = crate::mute_guard(true);
+
+    let mut shards = SHARDS.lock().unwrap_or_else(PoisonError::into_inner);
+    let buffers = std::mem::take(&mut shards.buffers);
+    #[cfg(feature = "timestamps")]
+    let start = shards.start.take();
+    drop(shards);
+
+    let shards = buffers
+        .iter()
+        .map(|shard| std::mem::take(&mut *shard.lock().unwrap_or_else(PoisonError::into_inner)))
+        .collect::<Vec<_>>();
 
-        crate::with_unmuted(f);
+    let mut order = shards
+        .iter()
+        .enumerate()
+        .flat_map(|(n, shard)| {
+            (shard.order.iter().enumerate()).map(move |(index, &position)| (position, n, index))
+        })
+        .collect::<Vec<_>>();
 
-        let snapshot = Snapshot {
-            events: s.borrow().events.clone(),
-        };
+    order.sort_unstable();
 
-        snapshot
-    })
+    let mut events = Events::new();
+    events.reserve(order.len());
+
+    #[cfg(feature = "timestamps")]
+    if let Some(start) = start {
+        events.start_clock_at(start);
+    }
+
+    #[cfg(feature = "backtrace")]
+    let mut backtraces = BTreeMap::new();
+
+    for (_, n, index) in order {
+        #[cfg(feature = "backtrace")]
+        if let Some(backtrace) = shards[n].state.backtraces.get(&index) {
+            backtraces.insert(events.len(), backtrace.clone());
+        }
+
+        events.push_from(&shards[n].state.events, index);
+    }
+
+    Snapshot {
+        events,
+        #[cfg(feature = "backtrace")]
+        backtraces,
+        peak_memory: SHARED_PEAK_MEMORY.load(Ordering::Relaxed),
+        max_bytes: shards.iter().find_map(|shard| shard.state.max_bytes),
+        skipped_allocs: shards.iter().map(|shard| shard.state.skipped_allocs).sum(),
+    }
+}
+
+/// Guard which stops recording into shared state, even if we are unwinding
+/// due to a panic.
+#[cfg(feature = "std")]
+struct SharedGuard;
+
+#[cfg(feature = "std")]
+impl Drop for SharedGuard {
+    fn drop(&mut self) {
+        SHARED_ACTIVE.store(false, Ordering::Release);
+    }
 }
 
 /// Structure containing all thread-local state required to use the
 /// single-threaded allocation checker.
+#[cfg(feature = "std")]
 pub struct State {
     /// Events collected.
     pub events: Events,
+    /// Backtraces captured for events, indexed by the position of the event
+    /// in [State::events].
+    #[cfg(feature = "backtrace")]
+    pub backtraces: BTreeMap<usize, Backtrace>,
+    /// The number of bytes currently live according to tracked allocations.
+    pub memory_used: usize,
+    /// The highest value `memory_used` has reached.
+    pub peak_memory: usize,
+    /// The memory budget enforced by the allocator, if any.
+    pub max_bytes: Option<usize>,
+    /// The number of allocations which were not recorded since they were
+    /// smaller than the minimum tracked size.
+    pub skipped_allocs: usize,
+    /// The number of nested calls to [with] currently running.
+    depth: usize,
 }
 
+#[cfg(feature = "std")]
 impl State {
     /// Construct new local state.
     pub const fn new() -> Self {
         Self {
             events: Events::new(),
+            #[cfg(feature = "backtrace")]
+            backtraces: BTreeMap::new(),
+            memory_used: 0,
+            peak_memory: 0,
+            max_bytes: None,
+            skipped_allocs: 0,
+            depth: 0,
         }
     }
 
@@ -369,11 +1499,17 @@ impl State {
         self.events.reserve(cap);
     }
 
-    /// Clear the current collection of events.
+    /// Clear the current collection of events and memory statistics.
     ///
     /// See [Events::clear] for more documentation.
     pub fn clear(&mut self) {
         self.events.clear();
+        #[cfg(feature = "backtrace")]
+        self.backtraces.clear();
+        self.memory_used = 0;
+        self.peak_memory = 0;
+        self.max_bytes = None;
+        self.skipped_allocs = 0;
     }
 
     /// Validate the current state.
@@ -384,10 +1520,20 @@ impl State {
     }
 }
 
+#[cfg(feature = "std")]
+impl Default for State {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// A type-erased pointer.
 /// The inner representation is specifically _not_ a raw pointer to avoid
 /// aliasing the pointers handled by the allocator.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Pointer(usize);
 
 impl Pointer {
@@ -401,9 +1547,104 @@ impl Pointer {
         Self(self.0.saturating_add(n))
     }
 
+    /// Add the given offset to the current pointer, returning `None` if the
+    /// result overflows.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::Pointer;
+    ///
+    /// assert_eq!(Some(Pointer::from(15)), Pointer::from(10).checked_add(5));
+    /// assert_eq!(None, Pointer::from(usize::MAX).checked_add(1));
+    /// ```
+    pub fn checked_add(self, n: usize) -> Option<Self> {
+        Some(Self(self.0.checked_add(n)?))
+    }
+
+    /// Shift the pointer by the difference between `old_base` and `new_base`,
+    /// saturating at the bounds of the address space.
+    pub(crate) fn rebase(self, old_base: Pointer, new_base: Pointer) -> Self {
+        if new_base >= old_base {
+            Self(self.0.saturating_add(new_base.0 - old_base.0))
+        } else {
+            Self(self.0.saturating_sub(old_base.0 - new_base.0))
+        }
+    }
+
     /// Test if pointer is aligned with the given argument.
     pub fn is_aligned_with(self, n: usize) -> bool {
-        self.0 % n == 0
+        self.0.is_multiple_of(n)
+    }
+
+    /// Get the number of bytes this pointer is past the closest preceding
+    /// boundary of the given alignment. This is zero if the pointer is aligned,
+    /// or if `align` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::Pointer;
+    ///
+    /// assert_eq!(0, Pointer::from(0x1000).align_offset(16));
+    /// assert_eq!(4, Pointer::from(0x1004).align_offset(16));
+    /// assert_eq!(0, Pointer::from(0x1004).align_offset(0));
+    /// ```
+    pub fn align_offset(self, align: usize) -> usize {
+        self.0.checked_rem(align).unwrap_or(0)
+    }
+
+    /// Get the number of bytes between this pointer and `other`, regardless
+    /// of which one comes first. This can't overflow.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Pointer, Region};
+    ///
+    /// let a = Region::new(0x1000.into(), 16, 8);
+    /// let b = Region::new(0x2010.into(), 16, 8);
+    ///
+    /// // `b` starts 4096 bytes after `a` ends.
+    /// assert_eq!(4096, a.end().distance_to(b.ptr));
+    /// assert_eq!(4096, b.ptr.distance_to(a.end()));
+    /// assert_eq!(usize::MAX, Pointer::from(0).distance_to(usize::MAX.into()));
+    /// ```
+    pub fn distance_to(self, other: Pointer) -> usize {
+        if self >= other {
+            self.0 - other.0
+        } else {
+            other.0 - self.0
+        }
+    }
+
+    /// Get the signed number of bytes this pointer is past `other`, which is
+    /// negative if it comes before `other`.
+    ///
+    /// Differences which don't fit in an `isize` saturate at [isize::MAX] or
+    /// [isize::MIN], so use [distance_to][Pointer::distance_to] to get the
+    /// exact distance between pointers more than half the address space apart.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::Pointer;
+    ///
+    /// let a = Pointer::from(0x1000);
+    /// let b = Pointer::from(0x1040);
+    ///
+    /// assert_eq!(0x40, b.offset_from(a));
+    /// assert_eq!(-0x40, a.offset_from(b));
+    ///
+    /// assert_eq!(isize::MAX, Pointer::from(usize::MAX).offset_from(0.into()));
+    /// assert_eq!(isize::MIN, Pointer::from(0).offset_from(usize::MAX.into()));
+    /// ```
+    pub fn offset_from(self, other: Pointer) -> isize {
+        if self >= other {
+            0isize.saturating_add_unsigned(self.0 - other.0)
+        } else {
+            0isize.saturating_sub_unsigned(other.0 - self.0)
+        }
     }
 }
 
@@ -413,12 +1654,93 @@ impl fmt::Display for Pointer {
     }
 }
 
+/// Format the address of the pointer in lowercase hexadecimal.
+///
+/// # Examples
+///
+/// ```rust
+/// let ptr = checkers::Pointer::from(0xbeef);
+/// assert_eq!("0xbeef", format!("{:#x}", ptr));
+/// assert_eq!("beef", format!("{:x}", ptr));
+/// ```
+impl fmt::LowerHex for Pointer {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, fmt)
+    }
+}
+
+/// Format the address of the pointer in uppercase hexadecimal.
+///
+/// # Examples
+///
+/// ```rust
+/// let ptr = checkers::Pointer::from(0xbeef);
+/// assert_eq!("0xBEEF", format!("{:#X}", ptr));
+/// ```
+impl fmt::UpperHex for Pointer {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, fmt)
+    }
+}
+
+/// Take the address of a raw pointer.
+///
+/// The conversion is purely numeric. A [Pointer] is an address stored as an
+/// integer, so it doesn't keep the provenance of the raw pointer it was
+/// constructed from.
+///
+/// # Examples
+///
+/// ```rust
+/// use checkers::Pointer;
+///
+/// let mut value = 42u8;
+/// let ptr: *mut u8 = &mut value;
+///
+/// assert_eq!(Pointer::from(ptr as usize), Pointer::from(ptr));
+/// assert_eq!(Pointer::from(ptr), Pointer::from(ptr as *const u8));
+/// ```
 impl From<*mut u8> for Pointer {
     fn from(value: *mut u8) -> Self {
         Self(value as usize)
     }
 }
 
+/// Take the address of a raw pointer, like the conversion from `*mut u8`.
+impl From<*const u8> for Pointer {
+    fn from(value: *const u8) -> Self {
+        Self(value as usize)
+    }
+}
+
+/// Construct a raw pointer with the address of a [Pointer].
+///
+/// The conversion is purely numeric and doesn't imply any provenance, so the
+/// resulting raw pointer can't be dereferenced even if the address is live.
+/// It's only suitable for comparisons, or for handing back to an allocator
+/// which identifies regions by address.
+///
+/// # Examples
+///
+/// ```rust
+/// let ptr = checkers::Pointer::from(0x1000);
+/// assert_eq!(0x1000, <*mut u8>::from(ptr) as usize);
+/// assert_eq!(ptr, checkers::Pointer::from(<*mut u8>::from(ptr)));
+/// ```
+impl From<Pointer> for *mut u8 {
+    fn from(value: Pointer) -> Self {
+        value.0 as *mut u8
+    }
+}
+
+/// Construct a raw pointer with the address of a [Pointer], like the
+/// conversion to `*mut u8`.
+impl From<Pointer> for *const u8 {
+    fn from(value: Pointer) -> Self {
+        value.0 as *const u8
+    }
+}
+
 impl From<usize> for Pointer {
     fn from(value: usize) -> Self {
         Self(value)
@@ -429,6 +1751,8 @@ impl From<usize> for Pointer {
 ///
 /// Zeroed allocation are guaranteed by the allocator to be zeroed.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[non_exhaustive]
 pub struct AllocZeroed {
     /// Indicates if the region was indeed zeroed.
@@ -444,12 +1768,42 @@ impl AllocZeroed {
     }
 }
 
+/// Description of an allocation which was refused since it would exceed the
+/// memory budget of the allocator.
+///
+/// See [Allocator::with_max_bytes].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[non_exhaustive]
+pub struct BudgetExceeded {
+    /// The number of bytes requested.
+    pub requested: usize,
+    /// The number of bytes live at the time of the request.
+    pub memory_used: usize,
+    /// The configured memory budget.
+    pub max_bytes: usize,
+}
+
+impl BudgetExceeded {
+    /// Construct a new refused allocation.
+    pub fn new(requested: usize, memory_used: usize, max_bytes: usize) -> Self {
+        Self {
+            requested,
+            memory_used,
+            max_bytes,
+        }
+    }
+}
+
 /// Description of a reallocation.
 ///
 /// Reallocations frees one location in memory and copies the shared prefix.
 /// If the region is the same size or smaller, it can usually be performed
 /// in-place.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[non_exhaustive]
 pub struct Realloc {
     /// Indicates if the subset of the old region was faithfully copied over
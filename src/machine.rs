@@ -1,15 +1,22 @@
 //! Fake machine implementation to validate an allocation history.
 
-use std::{
-    collections::{btree_map as map, BTreeMap},
+use alloc::{
+    collections::{BTreeMap, BTreeSet, BinaryHeap},
+    vec::Vec,
+};
+use core::{
+    alloc::{Layout, LayoutError},
+    cmp::Reverse,
     fmt,
+    ops::Bound,
 };
 
-use crate::{AllocZeroed, Event, Pointer, Realloc, Violation};
+use crate::{utils::Bytes, AllocZeroed, BudgetExceeded, Event, Pointer, Realloc, Violation};
 
 /// A memory region. Including its location in memory `ptr`, it's `size` and
 /// alignment `align`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub struct Region {
     /// The pointer of the allocation.
@@ -26,9 +33,245 @@ impl Region {
         Self { ptr, size, align }
     }
 
+    /// Construct a new region, checking that it's a valid allocation.
+    ///
+    /// # Errors
+    ///
+    /// Errors if `align` is not a power of two, if `size` is zero, or if the
+    /// region would extend past the end of the address space.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Region, RegionError};
+    ///
+    /// assert_eq!(Ok(Region::new(100.into(), 16, 8)), Region::try_new(100.into(), 16, 8));
+    /// assert_eq!(Err(RegionError::InvalidAlign { align: 0 }), Region::try_new(100.into(), 16, 0));
+    /// assert_eq!(Err(RegionError::InvalidAlign { align: 3 }), Region::try_new(100.into(), 16, 3));
+    /// assert_eq!(Err(RegionError::ZeroSize), Region::try_new(100.into(), 0, 8));
+    /// assert_eq!(Err(RegionError::Overflow), Region::try_new(100.into(), usize::MAX, 1));
+    /// ```
+    pub fn try_new(ptr: Pointer, size: usize, align: usize) -> Result<Self, RegionError> {
+        if !align.is_power_of_two() {
+            return Err(RegionError::InvalidAlign { align });
+        }
+
+        if size == 0 {
+            return Err(RegionError::ZeroSize);
+        }
+
+        if ptr.checked_add(size).is_none() {
+            return Err(RegionError::Overflow);
+        }
+
+        Ok(Self { ptr, size, align })
+    }
+
+    /// Construct a region at `ptr` with the size and alignment of the given
+    /// layout.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::Region;
+    /// use std::alloc::Layout;
+    ///
+    /// let layout = Layout::new::<u64>();
+    /// let region = Region::from_layout(100.into(), layout);
+    /// assert_eq!(Region::new(100.into(), 8, layout.align()), region);
+    /// ```
+    pub fn from_layout(ptr: Pointer, layout: Layout) -> Self {
+        Self {
+            ptr,
+            size: layout.size(),
+            align: layout.align(),
+        }
+    }
+
+    /// Convert the size and alignment of this region into a layout.
+    ///
+    /// # Errors
+    ///
+    /// Errors under the same conditions as [Layout::from_size_align], like if
+    /// the alignment is not a power of two.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::Region;
+    /// use std::alloc::Layout;
+    ///
+    /// let region = Region::new(100.into(), 16, 8);
+    /// assert_eq!(Ok(Layout::from_size_align(16, 8).unwrap()), region.to_layout());
+    ///
+    /// let region = Region::new(100.into(), 16, 3);
+    /// assert!(region.to_layout().is_err());
+    /// ```
+    pub fn to_layout(self) -> Result<Layout, LayoutError> {
+        Layout::from_size_align(self.size, self.align)
+    }
+
+    /// Get the exclusive upper bound of this region.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Pointer, Region};
+    ///
+    /// let region = Region::new(100.into(), 20, 1);
+    /// assert_eq!(Pointer::from(120), region.end());
+    /// ```
+    pub fn end(self) -> Pointer {
+        self.ptr.saturating_add(self.size)
+    }
+
+    /// Test if the given pointer is contained in this region.
+    ///
+    /// The upper bound is exclusive, so a zero-sized region contains nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::Region;
+    ///
+    /// let region = Region::new(100.into(), 20, 1);
+    /// assert!(!region.contains(99.into()));
+    /// assert!(region.contains(100.into()));
+    /// assert!(region.contains(119.into()));
+    /// assert!(!region.contains(120.into()));
+    ///
+    /// let region = Region::new(100.into(), 0, 1);
+    /// assert!(!region.contains(100.into()));
+    /// ```
+    pub fn contains(self, ptr: Pointer) -> bool {
+        self.ptr <= ptr && ptr < self.end()
+    }
+
     /// Test if this region overlaps with another region.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::Region;
+    ///
+    /// let small = Region::new(110.into(), 10, 1);
+    /// let large = Region::new(100.into(), 100, 1);
+    ///
+    /// assert!(small.overlaps(large));
+    /// assert!(large.overlaps(small));
+    /// ```
     pub fn overlaps(self, other: Self) -> bool {
-        self.ptr <= other.ptr && other.ptr < self.ptr.saturating_add(self.size)
+        self.ptr < other.end() && other.ptr < self.end()
+    }
+
+    /// Get the region where this region overlaps with another region, if any.
+    ///
+    /// The alignment of the returned region is taken from `self`. Regions which
+    /// are only adjacent to each other do not intersect.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::Region;
+    ///
+    /// let a = Region::new(100.into(), 100, 8);
+    /// let b = Region::new(150.into(), 100, 1);
+    ///
+    /// assert_eq!(Some(Region::new(150.into(), 50, 8)), a.intersection(b));
+    /// assert_eq!(Some(Region::new(150.into(), 50, 1)), b.intersection(a));
+    ///
+    /// let c = Region::new(200.into(), 10, 1);
+    /// assert_eq!(None, a.intersection(c));
+    /// ```
+    pub fn intersection(self, other: Self) -> Option<Region> {
+        if !self.overlaps(other) {
+            return None;
+        }
+
+        let ptr = Pointer::max(self.ptr, other.ptr);
+        let end = Pointer::min(self.end(), other.end());
+
+        Some(Region {
+            ptr,
+            size: end.0 - ptr.0,
+            align: self.align,
+        })
+    }
+
+    /// Test if this region is directly followed or preceded by another region,
+    /// so that one ends exactly where the other begins. Alignment is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::Region;
+    ///
+    /// let a = Region::new(100.into(), 100, 8);
+    /// let b = Region::new(200.into(), 10, 1);
+    ///
+    /// assert!(a.is_adjacent_to(b));
+    /// assert!(b.is_adjacent_to(a));
+    /// assert!(!a.is_adjacent_to(Region::new(210.into(), 10, 1)));
+    /// ```
+    pub fn is_adjacent_to(self, other: Self) -> bool {
+        self.end() == other.ptr || other.end() == self.ptr
+    }
+
+    /// Merge this region with another region which is adjacent to it, into a
+    /// single region covering both.
+    ///
+    /// Returns `None` if the regions aren't adjacent, or if they don't have the
+    /// same alignment.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::Region;
+    ///
+    /// let a = Region::new(100.into(), 100, 4);
+    /// let b = Region::new(200.into(), 50, 4);
+    ///
+    /// assert_eq!(Some(Region::new(100.into(), 150, 4)), a.coalesce(b));
+    /// assert_eq!(Some(Region::new(100.into(), 150, 4)), b.coalesce(a));
+    ///
+    /// let c = Region::new(200.into(), 50, 8);
+    /// assert_eq!(None, a.coalesce(c));
+    /// ```
+    pub fn coalesce(self, other: Self) -> Option<Region> {
+        if !self.is_adjacent_to(other) || self.align != other.align {
+            return None;
+        }
+
+        Some(Region {
+            ptr: Pointer::min(self.ptr, other.ptr),
+            size: self.size.checked_add(other.size)?,
+            align: self.align,
+        })
+    }
+
+    /// Move the region from being relative to `old_base` to being relative to
+    /// `new_base`, keeping its offset from the base.
+    ///
+    /// The shifted pointer saturates at the bounds of the address space. A
+    /// region which is shifted past the end of it is reported as a
+    /// [RegionOverflow][Violation::RegionOverflow] if it's validated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::Region;
+    ///
+    /// let region = Region::new(0x1010.into(), 16, 8);
+    ///
+    /// assert_eq!(Region::new(0x10.into(), 16, 8), region.rebase(0x1000.into(), 0.into()));
+    /// assert_eq!(Region::new(0x2010.into(), 16, 8), region.rebase(0x1000.into(), 0x2000.into()));
+    /// assert_eq!(Region::new(0.into(), 16, 8), region.rebase(0x2000.into(), 0.into()));
+    /// ```
+    pub fn rebase(self, old_base: Pointer, new_base: Pointer) -> Self {
+        Self {
+            ptr: self.ptr.rebase(old_base, new_base),
+            ..self
+        }
     }
 
     /// Test if regions are the same (minus alignment).
@@ -37,191 +280,2398 @@ impl Region {
     }
 }
 
+/// Generate an arbitrary region.
+///
+/// The alignment is always a power of two, so that most generated regions are
+/// structurally valid.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Region {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let ptr = Pointer::arbitrary(u)?;
+        let size = usize::arbitrary(u)?;
+        let align = 1 << (u8::arbitrary(u)? % 16);
+        Ok(Self { ptr, size, align })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Pointer::size_hint(depth),
+            usize::size_hint(depth),
+            u8::size_hint(depth),
+        ])
+    }
+}
+
+/// Format the region with its addresses in hexadecimal.
+///
+/// # Examples
+///
+/// ```rust
+/// let region = checkers::Region::new(0x1000.into(), 16, 8);
+/// assert_eq!("0x1000-0x1010 (size: 16 B, align: 8)", region.to_string());
+///
+/// let region = checkers::Region::new(0x1000.into(), 0x100000, 8);
+/// assert_eq!("0x1000-0x101000 (size: 1.00 MiB, align: 8)", region.to_string());
+/// ```
 impl fmt::Display for Region {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             fmt,
-            "{}-{} (size: {}, align: {})",
+            "{:#x}-{:#x} (size: {}, align: {})",
             self.ptr,
-            self.ptr.saturating_add(self.size),
-            self.size,
+            self.end(),
+            Bytes(self.size),
             self.align,
         )
     }
 }
 
+/// Error raised when constructing a region with [Region::try_new].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RegionError {
+    /// The alignment is not a power of two.
+    InvalidAlign {
+        /// The alignment found.
+        align: usize,
+    },
+    /// The region is zero-sized.
+    ZeroSize,
+    /// The region extends past the end of the address space.
+    Overflow,
+}
+
+impl fmt::Display for RegionError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidAlign { align } => {
+                write!(fmt, "Alignment {} is not a power of two", align)
+            }
+            Self::ZeroSize => write!(fmt, "Region is zero-sized"),
+            Self::Overflow => write!(fmt, "Region extends past the end of the address space"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RegionError {}
+
+/// Fragmentation metrics of the live regions in a [Machine].
+///
+/// A gap is the space between the end of a live region and the start of the
+/// next one. Space before the lowest and after the highest live region is not
+/// counted.
+///
+/// See [Machine::fragmentation].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Fragmentation {
+    /// The size of the largest gap.
+    pub largest_gap: usize,
+    /// The sum of the sizes of all gaps.
+    pub total_gap: usize,
+    /// The number of gaps.
+    pub gap_count: usize,
+}
+
+/// A saved copy of the regions and memory statistics of a [Machine], as
+/// produced by [Machine::snapshot_state].
+#[derive(Debug, Clone)]
+pub struct MachineState {
+    regions: BTreeMap<Pointer, Region>,
+    memory_used: usize,
+    peak_memory: usize,
+    peak_region_count: usize,
+    total_allocated: usize,
+    tombstones: Vec<(Region, usize)>,
+    established: BTreeMap<Pointer, usize>,
+    reuse_counts: BTreeMap<Pointer, usize>,
+    stack: Vec<Pointer>,
+    allocated: BTreeSet<Pointer>,
+    adopted_frees: Vec<Region>,
+    zero_reallocs: Vec<Region>,
+    index: usize,
+}
+
+/// The order in which a [Machine] permits regions to be freed, see
+/// [Machine::with_free_order].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FreeOrder {
+    /// Regions can be freed in any order.
+    #[default]
+    Any,
+    /// Regions have to be freed in the reverse order they were allocated in,
+    /// like in a stack-based arena allocator.
+    Lifo,
+}
+
 /// Fake machine implementation to validate an allocation history.
-#[derive(Default)]
+#[derive(Debug)]
 pub struct Machine {
     /// Used memory regions.
     regions: BTreeMap<Pointer, Region>,
     /// Current memory used according to allocations.
-    pub memory_used: usize,
+    memory_used: usize,
+    /// The highest value `memory_used` has reached.
+    peak_memory: usize,
+    /// The highest number of regions which have been live at once.
+    peak_region_count: usize,
+    /// The total number of bytes ever allocated, including growth through
+    /// reallocations.
+    total_allocated: usize,
+    /// If frees of a part of an existing region are permitted.
+    partial_frees: bool,
+    /// If frees with a weaker alignment than the existing region are
+    /// permitted.
+    lenient_free_align: bool,
+    /// The largest alignment permitted, if any.
+    max_align: Option<usize>,
+    /// The size of a page which allocations shouldn't straddle, if any.
+    page_size: Option<usize>,
+    /// The number of events for which freed regions are kept as tombstones,
+    /// if enabled.
+    tombstone_window: Option<usize>,
+    /// Recently freed regions, together with the index of the event which
+    /// freed them.
+    tombstones: Vec<(Region, usize)>,
+    /// If the index of the event which established each live region is kept.
+    provenance: bool,
+    /// The index of the event which established each live region, if
+    /// provenance is kept.
+    established: BTreeMap<Pointer, usize>,
+    /// If the number of allocations at each address is counted.
+    reuse_tracking: bool,
+    /// The number of allocations which have started at each address, if
+    /// counted.
+    reuse_counts: BTreeMap<Pointer, usize>,
+    /// The order in which regions have to be freed.
+    free_order: FreeOrder,
+    /// The pointers of live regions in the order they were allocated in, if
+    /// frees have to be in LIFO order.
+    stack: Vec<Pointer>,
+    /// If frees of regions which were never allocated are permitted.
+    adopt_frees: bool,
+    /// The pointers of every region allocated, if frees of regions which were
+    /// never allocated are permitted.
+    allocated: BTreeSet<Pointer>,
+    /// Frees of regions which were never allocated.
+    adopted_frees: Vec<Region>,
+    /// If reallocations to a size of zero are reported instead of being
+    /// treated as frees.
+    strict_zero_reallocs: bool,
+    /// Regions freed through a reallocation to a size of zero.
+    zero_reallocs: Vec<Region>,
+    /// The number of events pushed.
+    index: usize,
+}
+
+impl Default for Machine {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Machine {
-    /// Push an event into the machine.
+    /// Construct a new machine with no live regions, which is the same as
+    /// [Machine::default]. Unlike it, this can be used in a const context.
     ///
-    /// # Examples
+    /// Live regions are kept in a tree keyed by their pointer, which doesn't
+    /// need any capacity to be reserved up front.
     ///
-    /// Checks for a double-free:
+    /// # Examples
     ///
     /// ```rust
     /// use checkers::{Event::*, Region, Machine};
     ///
-    /// let mut machine = Machine::default();
+    /// let mut machine = Machine::new();
+    /// assert!(machine.is_clean());
     ///
-    /// assert!(machine.push(Alloc(Region::new(0.into(), 2, 1))).is_ok());
-    /// assert!(machine.push(Free(Region::new(0.into(), 2, 1))).is_ok());
-    /// assert!(machine.push(Free(Region::new(0.into(), 2, 1))).is_err());
+    /// assert!(machine.push(Alloc(Region::new(10.into(), 10, 1))).is_ok());
+    /// assert_eq!(10, machine.memory_used());
     /// ```
+    pub const fn new() -> Self {
+        Self {
+            regions: BTreeMap::new(),
+            memory_used: 0,
+            peak_memory: 0,
+            peak_region_count: 0,
+            total_allocated: 0,
+            partial_frees: false,
+            lenient_free_align: false,
+            max_align: None,
+            page_size: None,
+            tombstone_window: None,
+            tombstones: Vec::new(),
+            provenance: false,
+            established: BTreeMap::new(),
+            reuse_tracking: false,
+            reuse_counts: BTreeMap::new(),
+            free_order: FreeOrder::Any,
+            stack: Vec::new(),
+            adopt_frees: false,
+            allocated: BTreeSet::new(),
+            adopted_frees: Vec::new(),
+            strict_zero_reallocs: false,
+            zero_reallocs: Vec::new(),
+            index: 0,
+        }
+    }
+
+    /// Configure if the machine permits freeing only part of an existing
+    /// region. Defaults to `false`.
     ///
-    /// Check for a misaligned allocation:
+    /// When enabled, freeing a prefix or suffix of a region shrinks it, and
+    /// freeing a chunk in the middle of a region splits it in two. Frees which
+    /// extend past the end of the region they start in are still a violation.
+    ///
+    /// # Examples
     ///
     /// ```rust
-    /// use checkers::{Event::*, Region, Machine, Violation};
+    /// use checkers::{Event::*, Region, Machine};
     ///
-    /// let mut machine = Machine::default();
-    /// let requested = Region::new(5.into(), 2, 4);
+    /// let mut machine = Machine::default().with_partial_frees(true);
     ///
+    /// assert!(machine.push(Alloc(Region::new(100.into(), 100, 1))).is_ok());
+    /// assert!(machine.push(Free(Region::new(100.into(), 50, 1))).is_ok());
+    /// assert!(machine.push(Free(Region::new(160.into(), 20, 1))).is_ok());
+    ///
+    /// assert_eq!(30, machine.memory_used());
     /// assert_eq!(
-    ///     Err(Violation::MisalignedAlloc { requested }),
-    ///     machine.push(Alloc(requested))
+    ///     vec![Region::new(150.into(), 10, 1), Region::new(180.into(), 20, 1)],
+    ///     machine.trailing_regions()
     /// );
     /// ```
+    pub fn with_partial_frees(self, partial_frees: bool) -> Self {
+        Self {
+            partial_frees,
+            ..self
+        }
+    }
+
+    /// Configure if the machine permits freeing a region with a weaker
+    /// alignment than it was allocated with. Defaults to `false`, where the
+    /// alignments have to match exactly.
     ///
-    /// Tries to deallocate part of other region:
+    /// Some allocators reconstruct the layout passed to dealloc, and end up
+    /// with a smaller alignment than the region was allocated with. When
+    /// enabled, such frees are accepted as long as the alignment of the
+    /// existing region is a multiple of the freed one. This also applies to
+    /// the region freed by a reallocation. A larger alignment is still
+    /// reported as [MisalignedFree][Violation::MisalignedFree].
+    ///
+    /// # Examples
     ///
     /// ```rust
     /// use checkers::{Event::*, Region, Machine, Violation};
     ///
-    /// let mut machine = Machine::default();
-    /// let existing = Region::new(100.into(), 100, 1);
+    /// let mut machine = Machine::default().with_lenient_free_align(true);
+    /// let existing = Region::new(0x100.into(), 16, 8);
     ///
     /// assert!(machine.push(Alloc(existing)).is_ok());
     ///
-    /// let requested = Region::new(150.into(), 50, 1);
-    /// assert_eq!(
-    ///     Err(Violation::MissingFree { requested }),
-    ///     machine.push(Free(requested))
-    /// );
+    /// let requested = Region::new(0x100.into(), 16, 16);
     ///
-    /// let requested = Region::new(100.into(), 50, 1);
     /// assert_eq!(
-    ///     Err(Violation::IncompleteFree { requested, existing }),
+    ///     Err(Violation::MisalignedFree { requested, existing }),
     ///     machine.push(Free(requested))
     /// );
+    ///
+    /// assert!(machine.push(Free(Region::new(0x100.into(), 16, 4))).is_ok());
+    /// assert!(machine.is_clean());
     /// ```
-    pub fn push(&mut self, event: Event) -> Result<(), Violation> {
-        match event {
-            Event::Alloc(requested) => {
-                self.alloc(requested)?;
-            }
-            Event::Free(requested) => {
-                self.free(requested)?;
-            }
-            Event::AllocZeroed(AllocZeroed {
-                is_zeroed,
-                alloc: requested,
-            }) => {
-                if let Some(false) = is_zeroed {
-                    return Err(Violation::NonZeroedAlloc { requested });
-                }
-
-                self.alloc(requested)?;
-            }
-            Event::Realloc(Realloc {
-                is_relocated,
-                free,
-                alloc,
-            }) => {
-                if let Some(false) = is_relocated {
-                    return Err(Violation::NonCopiedRealloc { free, alloc });
-                }
-
-                self.free(free)?;
-                self.alloc(alloc)?;
-            }
-            Event::ReallocNull => {
-                return Err(Violation::ReallocNull {});
-            }
-            // Note: the following have no effects, outside of what the erorrs
-            // mean to the caller of the allocator. They could for example
-            // decide to gracefully signal OOM (https://github.com/rust-lang/rust/issues/48043)
-            // or panic.
-            Event::AllocFailed => (),
-            Event::AllocZeroedFailed => (),
-            Event::ReallocFailed => (),
+    pub fn with_lenient_free_align(self, lenient_free_align: bool) -> Self {
+        Self {
+            lenient_free_align,
+            ..self
         }
-
-        Ok(())
     }
 
-    /// Process an allocation.
-    fn alloc(&mut self, requested: Region) -> Result<(), Violation> {
-        if !requested.ptr.is_aligned_with(requested.align) {
-            return Err(Violation::MisalignedAlloc { requested });
-        }
-
-        if let Some(existing) = find_region_overlaps(&self.regions, requested).next() {
-            return Err(Violation::ConflictingAlloc {
-                requested,
-                existing,
-            });
+    /// Configure the largest alignment that allocations and frees may request.
+    /// Defaults to unlimited.
+    ///
+    /// Regions with a larger alignment are reported as
+    /// [ExcessiveAlign][Violation::ExcessiveAlign]. This can be used to catch
+    /// code which relies on an alignment the underlying allocator of some
+    /// targets doesn't provide.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Violation};
+    ///
+    /// let mut machine = Machine::default().with_max_align(16);
+    ///
+    /// assert!(machine.push(Alloc(Region::new(0x100.into(), 16, 16))).is_ok());
+    ///
+    /// let requested = Region::new(0x200.into(), 32, 32);
+    ///
+    /// assert_eq!(
+    ///     Err(Violation::ExcessiveAlign { requested, max: 16 }),
+    ///     machine.push(Alloc(requested))
+    /// );
+    /// ```
+    pub fn with_max_align(self, max_align: usize) -> Self {
+        Self {
+            max_align: Some(max_align),
+            ..self
         }
-
-        self.memory_used = self.memory_used.saturating_add(requested.size);
-        debug_assert!(self.regions.insert(requested.ptr, requested).is_none());
-        Ok(())
     }
 
-    /// Process a free.
-    fn free(&mut self, requested: Region) -> Result<(), Violation> {
-        let entry = if let map::Entry::Occupied(entry) = self.regions.entry(requested.ptr) {
-            entry
-        } else {
-            return Err(Violation::MissingFree { requested });
-        };
-
-        let existing = *entry.get();
+    /// Report allocations which cross a boundary between two `page_size`-byte
+    /// pages even though they would fit in a single page as
+    /// [CrossesPageBoundary][Violation::CrossesPageBoundary]. Disabled by
+    /// default.
+    ///
+    /// This can be used to catch buffers which are expected to fit in one
+    /// page, like the ones used for DMA. Regions larger than a page
+    /// necessarily span multiple pages, and are never reported. The
+    /// allocation is still recorded even though a violation is reported.
+    ///
+    /// The page size must be a power of two, like `0x1000` below. A page size
+    /// of zero disables the check.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if `page_size` is neither zero nor a power of
+    /// two.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Violation};
+    ///
+    /// let mut machine = Machine::default().with_page_size(0x1000);
+    ///
+    /// assert!(machine.push(Alloc(Region::new(0x1000.into(), 0x1000, 1))).is_ok());
+    /// assert!(machine.push(Alloc(Region::new(0x2800.into(), 0x2000, 1))).is_ok());
+    ///
+    /// let requested = Region::new(0x5f00.into(), 0x200, 1);
+    ///
+    /// assert_eq!(
+    ///     Err(Violation::CrossesPageBoundary { requested, page_size: 0x1000 }),
+    ///     machine.push(Alloc(requested))
+    /// );
+    ///
+    /// assert!(machine.push(Free(requested)).is_ok());
+    /// ```
+    pub fn with_page_size(self, page_size: usize) -> Self {
+        debug_assert!(
+            page_size == 0 || page_size.is_power_of_two(),
+            "page size must be a power of two"
+        );
 
-        if !existing.is_same_region_as(requested) {
-            return Err(Violation::IncompleteFree {
-                requested,
-                existing,
-            });
+        Self {
+            page_size: Some(page_size),
+            ..self
         }
+    }
 
-        if existing.align != requested.align {
-            return Err(Violation::MisalignedFree {
-                requested,
-                existing,
-            });
+    /// Keep freed regions as tombstones for a `window` of subsequent events,
+    /// and report allocations which reuse any part of them within it as
+    /// [QuickReuse][Violation::QuickReuse]. Disabled by default.
+    ///
+    /// Reusing memory immediately is valid, but can hide a use after free in
+    /// the code being tested, since the dangling pointer still points into
+    /// live memory. The freed side of a reallocation which moves a region is
+    /// also kept as a tombstone.
+    ///
+    /// Events are indexed in the order they were pushed into the machine.
+    /// The allocation is still recorded even though a violation is reported.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Violation};
+    ///
+    /// let mut machine = Machine::default().with_tombstones(2);
+    /// let region = Region::new(100.into(), 20, 1);
+    ///
+    /// assert!(machine.push(Alloc(region)).is_ok());
+    /// assert!(machine.push(Free(region)).is_ok());
+    ///
+    /// assert_eq!(
+    ///     Err(Violation::QuickReuse { requested: region, freed_at: 1 }),
+    ///     machine.push(Alloc(region))
+    /// );
+    ///
+    /// assert!(machine.push(Free(region)).is_ok());
+    /// assert!(machine.push(Alloc(Region::new(200.into(), 20, 1))).is_ok());
+    /// assert!(machine.push(Alloc(Region::new(300.into(), 20, 1))).is_ok());
+    ///
+    /// // The tombstone has expired.
+    /// assert!(machine.push(Alloc(region)).is_ok());
+    /// ```
+    pub fn with_tombstones(self, window: usize) -> Self {
+        Self {
+            tombstone_window: Some(window),
+            ..self
+        }
+    }
+
+    /// Configure if the machine keeps track of the index of the event which
+    /// established each live region, so that it can be looked up with
+    /// [established_at][Machine::established_at]. Defaults to `false`.
+    ///
+    /// This is useful to explain where the existing region of a violation like
+    /// [ConflictingAlloc][Violation::ConflictingAlloc] came from. Events are
+    /// indexed in the order they were pushed into the machine.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Violation};
+    ///
+    /// let mut machine = Machine::default().with_provenance(true);
+    /// let existing = Region::new(100.into(), 100, 1);
+    /// let requested = Region::new(150.into(), 100, 1);
+    ///
+    /// let violations = machine.push_all(vec![
+    ///     Alloc(Region::new(400.into(), 10, 1)),
+    ///     Alloc(existing),
+    ///     Alloc(requested),
+    /// ]);
+    ///
+    /// assert_eq!(vec![(2, Violation::ConflictingAlloc { requested, existing })], violations);
+    /// assert_eq!(Some(1), machine.established_at(existing.ptr));
+    /// ```
+    pub fn with_provenance(self, provenance: bool) -> Self {
+        Self { provenance, ..self }
+    }
+
+    /// Configure the order in which the machine permits regions to be
+    /// freed. Defaults to [FreeOrder::Any].
+    ///
+    /// With [FreeOrder::Lifo], freeing any region but the most recently
+    /// allocated live region raises [OutOfOrderFree][Violation::OutOfOrderFree].
+    /// A reallocation keeps the position of the region it reallocates.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, FreeOrder, Region, Machine, Violation};
+    ///
+    /// let mut machine = Machine::default().with_free_order(FreeOrder::Lifo);
+    /// let a = Region::new(100.into(), 10, 1);
+    /// let b = Region::new(200.into(), 10, 1);
+    ///
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    /// assert!(machine.push(Alloc(b)).is_ok());
+    ///
+    /// assert_eq!(
+    ///     Err(Violation::OutOfOrderFree { requested: a, expected: b }),
+    ///     machine.push(Free(a))
+    /// );
+    ///
+    /// assert!(machine.push(Free(b)).is_ok());
+    /// assert!(machine.push(Free(a)).is_ok());
+    /// ```
+    pub fn with_free_order(self, free_order: FreeOrder) -> Self {
+        Self { free_order, ..self }
+    }
+
+    /// Configure if the machine permits freeing regions at pointers which it
+    /// has never seen allocated. Defaults to `false`.
+    ///
+    /// This is useful if recording starts after some regions were already
+    /// allocated, like when the allocator is installed late in a large
+    /// application. Instead of raising [MissingFree][Violation::MissingFree],
+    /// such frees are accepted and collected in
+    /// [adopted_frees][Machine::adopted_frees]. Reallocations of such regions
+    /// are treated as allocations of the new region.
+    ///
+    /// Freeing a pointer which was allocated before, like in a double free, or
+    /// a region which overlaps with a live region, is still reported.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Realloc, Violation};
+    ///
+    /// let mut machine = Machine::default().with_adopted_frees(true);
+    /// let a = Region::new(100.into(), 10, 1);
+    /// let b = Region::new(200.into(), 20, 1);
+    /// let c = Region::new(300.into(), 40, 1);
+    ///
+    /// assert!(machine.push(Free(a)).is_ok());
+    /// assert!(machine.push(Realloc(Realloc::new(None, b, c))).is_ok());
+    /// assert_eq!(&[a, b][..], machine.adopted_frees());
+    ///
+    /// assert!(machine.push(Free(c)).is_ok());
+    /// assert_eq!(
+    ///     Err(Violation::MissingFree { requested: c }),
+    ///     machine.push(Free(c))
+    /// );
+    /// ```
+    pub fn with_adopted_frees(self, adopt_frees: bool) -> Self {
+        Self {
+            adopt_frees,
+            ..self
+        }
+    }
+
+    /// Configure if the machine counts how many allocations have started at
+    /// each address over its history, so that they can be looked up with
+    /// [reuse_counts][Machine::reuse_counts]. Defaults to `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Realloc};
+    ///
+    /// let mut machine = Machine::default().with_reuse_counts(true);
+    /// let a = Region::new(100.into(), 10, 1);
+    /// let b = Region::new(200.into(), 20, 1);
+    ///
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    /// assert!(machine.push(Free(a)).is_ok());
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    /// assert!(machine.push(Realloc(Realloc::new(None, a, b))).is_ok());
+    ///
+    /// assert_eq!(Some(&2), machine.reuse_counts().get(&a.ptr));
+    /// assert_eq!(Some(&1), machine.reuse_counts().get(&b.ptr));
+    /// ```
+    pub fn with_reuse_counts(self, reuse_tracking: bool) -> Self {
+        Self {
+            reuse_tracking,
+            ..self
+        }
+    }
+
+    /// Configure if reallocations to a size of zero are reported as
+    /// [ReallocToZero][Violation::ReallocToZero]. Defaults to `false`.
+    ///
+    /// Such a reallocation is undefined behavior in Rust, while some libc
+    /// implementations treat it as freeing the region. By default the
+    /// machine follows the latter: the region is freed like by
+    /// [Free][Event::Free], including the check for a matching live region,
+    /// and is collected in [zero_reallocs][Machine::zero_reallocs] instead of
+    /// leaving a zero-sized region behind. In strict mode the region is left
+    /// untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Realloc, Violation};
+    ///
+    /// let a = Region::new(100.into(), 10, 1);
+    /// let realloc = Realloc::new(None, a, Region::new(100.into(), 0, 1));
+    ///
+    /// let mut machine = Machine::default();
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    /// assert!(machine.push(Realloc(realloc)).is_ok());
+    /// assert_eq!(0, machine.memory_used());
+    /// assert_eq!(&[a][..], machine.zero_reallocs());
+    ///
+    /// let mut machine = Machine::default().with_strict_zero_reallocs(true);
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    ///
+    /// assert_eq!(
+    ///     Err(Violation::ReallocToZero { free: a }),
+    ///     machine.push(Realloc(realloc))
+    /// );
+    /// assert_eq!(10, machine.memory_used());
+    /// ```
+    pub fn with_strict_zero_reallocs(self, strict_zero_reallocs: bool) -> Self {
+        Self {
+            strict_zero_reallocs,
+            ..self
+        }
+    }
+
+    /// Clear the machine of all regions and memory statistics, so that it can
+    /// be reused to replay another history.
+    ///
+    /// Configuration, like [with_partial_frees][Machine::with_partial_frees],
+    /// is retained.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    /// let region = Region::new(100.into(), 20, 1);
+    ///
+    /// assert!(machine.push(Alloc(region)).is_ok());
+    /// machine.clear();
+    ///
+    /// assert_eq!(0, machine.memory_used());
+    /// assert_eq!(0, machine.peak_memory());
+    /// assert!(machine.trailing_regions().is_empty());
+    /// assert!(machine.push(Alloc(region)).is_ok());
+    /// ```
+    pub fn clear(&mut self) {
+        self.regions.clear();
+        self.memory_used = 0;
+        self.peak_memory = 0;
+        self.peak_region_count = 0;
+        self.total_allocated = 0;
+        self.tombstones.clear();
+        self.established.clear();
+        self.reuse_counts.clear();
+        self.stack.clear();
+        self.allocated.clear();
+        self.adopted_frees.clear();
+        self.zero_reallocs.clear();
+        self.index = 0;
+    }
+
+    /// Save the regions and memory statistics of the machine, so that they can
+    /// be restored with [restore][Machine::restore].
+    ///
+    /// This clones every live region, so it takes time and memory linear in
+    /// the number of them. It's still cheaper than replaying a long history
+    /// again to get back to the same state.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    /// let a = Region::new(100.into(), 20, 1);
+    /// let b = Region::new(200.into(), 20, 1);
+    ///
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    /// let state = machine.snapshot_state();
+    ///
+    /// assert!(machine.push(Free(a)).is_ok());
+    /// assert!(machine.push(Alloc(b)).is_ok());
+    ///
+    /// machine.restore(state);
+    /// assert_eq!(vec![a], machine.trailing_regions());
+    /// assert_eq!(20, machine.memory_used());
+    /// ```
+    pub fn snapshot_state(&self) -> MachineState {
+        MachineState {
+            regions: self.regions.clone(),
+            memory_used: self.memory_used,
+            peak_memory: self.peak_memory,
+            peak_region_count: self.peak_region_count,
+            total_allocated: self.total_allocated,
+            tombstones: self.tombstones.clone(),
+            established: self.established.clone(),
+            reuse_counts: self.reuse_counts.clone(),
+            stack: self.stack.clone(),
+            allocated: self.allocated.clone(),
+            adopted_frees: self.adopted_frees.clone(),
+            zero_reallocs: self.zero_reallocs.clone(),
+            index: self.index,
+        }
+    }
+
+    /// Restore regions and memory statistics saved with
+    /// [snapshot_state][Machine::snapshot_state].
+    ///
+    /// Configuration, like [with_partial_frees][Machine::with_partial_frees],
+    /// is not part of the saved state and is left as is.
+    pub fn restore(&mut self, state: MachineState) {
+        self.regions = state.regions;
+        self.memory_used = state.memory_used;
+        self.peak_memory = state.peak_memory;
+        self.peak_region_count = state.peak_region_count;
+        self.total_allocated = state.total_allocated;
+        self.tombstones = state.tombstones;
+        self.established = state.established;
+        self.reuse_counts = state.reuse_counts;
+        self.stack = state.stack;
+        self.allocated = state.allocated;
+        self.adopted_frees = state.adopted_frees;
+        self.zero_reallocs = state.zero_reallocs;
+        self.index = state.index;
+    }
+
+    /// Merge the live regions of `other` into this machine.
+    ///
+    /// This permits validating separate histories, like the events of
+    /// different threads, independently and combining the results afterwards.
+    ///
+    /// Memory used and total bytes allocated are added together. Since the
+    /// relative order of the histories is unknown, the peak memory is the
+    /// highest of the peaks of either machine and the combined memory used.
+    ///
+    /// The history of `other` is treated as if it was pushed after the
+    /// history of this machine. Event indices of `other`, like the ones
+    /// reported by [established_at][Machine::established_at] and kept for
+    /// tombstones, are offset by the number of events pushed into this
+    /// machine. If frees have to be in LIFO order, the live regions of `other`
+    /// count as more recently allocated, so they have to be freed first. They
+    /// keep their own order if `other` required LIFO frees too, and are
+    /// ordered by address otherwise.
+    ///
+    /// The machine is left unchanged if a region in `other` conflicts with a
+    /// region in this machine.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Violation};
+    ///
+    /// let mut a = Machine::default();
+    /// assert!(a.push(Alloc(Region::new(100.into(), 20, 1))).is_ok());
+    ///
+    /// let mut b = Machine::default();
+    /// assert!(b.push(Alloc(Region::new(200.into(), 10, 1))).is_ok());
+    ///
+    /// assert!(a.merge(b).is_ok());
+    /// assert_eq!(30, a.memory_used());
+    /// assert_eq!(2, a.regions().count());
+    /// assert_eq!(2, a.events_processed());
+    ///
+    /// let mut c = Machine::default();
+    /// assert!(c.push(Alloc(Region::new(110.into(), 20, 1))).is_ok());
+    ///
+    /// assert_eq!(
+    ///     Err(Violation::ConflictingAlloc {
+    ///         requested: Region::new(110.into(), 20, 1),
+    ///         existing: Region::new(100.into(), 20, 1),
+    ///     }),
+    ///     a.merge(c)
+    /// );
+    /// assert_eq!(30, a.memory_used());
+    /// ```
+    pub fn merge(&mut self, other: Machine) -> Result<(), Violation> {
+        for requested in other.regions() {
+            if let Some(existing) = find_region_overlaps(&self.regions, requested).next() {
+                return Err(Violation::ConflictingAlloc {
+                    requested,
+                    existing,
+                });
+            }
+        }
+
+        let offset = self.index;
+
+        // Note: the allocation order of the regions in `other` is only known
+        // if it required LIFO frees as well, otherwise they are ordered by
+        // address.
+        if other.free_order == FreeOrder::Lifo {
+            self.stack.extend(other.stack);
+        } else if self.free_order == FreeOrder::Lifo {
+            self.stack.extend(other.regions.keys().copied());
+        }
+
+        self.regions.extend(other.regions);
+        self.established.extend(
+            other
+                .established
+                .into_iter()
+                .map(|(ptr, index)| (ptr, index.saturating_add(offset))),
+        );
+        self.tombstones.extend(
+            other
+                .tombstones
+                .into_iter()
+                .map(|(region, index)| (region, index.saturating_add(offset))),
+        );
+        self.allocated.extend(other.allocated);
+        self.adopted_frees.extend(other.adopted_frees);
+        self.zero_reallocs.extend(other.zero_reallocs);
+
+        for (ptr, count) in other.reuse_counts {
+            let total = self.reuse_counts.entry(ptr).or_default();
+            *total = total.saturating_add(count);
+        }
+
+        self.memory_used = self.memory_used.saturating_add(other.memory_used);
+        self.total_allocated = self.total_allocated.saturating_add(other.total_allocated);
+        self.peak_memory = usize::max(
+            self.memory_used,
+            usize::max(self.peak_memory, other.peak_memory),
+        );
+        self.peak_region_count = usize::max(
+            self.regions.len(),
+            usize::max(self.peak_region_count, other.peak_region_count),
+        );
+        self.index = self.index.saturating_add(other.index);
+        Ok(())
+    }
+
+    /// Push an event into the machine.
+    ///
+    /// # Examples
+    ///
+    /// Checks for a double-free:
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    ///
+    /// assert!(machine.push(Alloc(Region::new(0.into(), 2, 1))).is_ok());
+    /// assert!(machine.push(Free(Region::new(0.into(), 2, 1))).is_ok());
+    /// assert!(machine.push(Free(Region::new(0.into(), 2, 1))).is_err());
+    /// ```
+    ///
+    /// Check for a misaligned allocation:
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Violation};
+    ///
+    /// let mut machine = Machine::default();
+    /// let requested = Region::new(5.into(), 2, 4);
+    ///
+    /// assert_eq!(
+    ///     Err(Violation::MisalignedAlloc { requested }),
+    ///     machine.push(Alloc(requested))
+    /// );
+    /// ```
+    ///
+    /// Check for a zero-sized allocation:
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Violation};
+    ///
+    /// let mut machine = Machine::default();
+    /// let requested = Region::new(10.into(), 0, 1);
+    ///
+    /// assert_eq!(
+    ///     Err(Violation::ZeroSizedAlloc { requested }),
+    ///     machine.push(Alloc(requested))
+    /// );
+    /// assert_eq!(
+    ///     Err(Violation::MissingFree { requested }),
+    ///     machine.push(Free(requested))
+    /// );
+    /// ```
+    ///
+    /// Check for an alignment which is not a power of two:
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Violation};
+    ///
+    /// let mut machine = Machine::default();
+    /// let requested = Region::new(9.into(), 2, 3);
+    ///
+    /// assert_eq!(
+    ///     Err(Violation::InvalidAlign { requested }),
+    ///     machine.push(Alloc(requested))
+    /// );
+    /// ```
+    ///
+    /// Check for a region which extends past the end of the address space:
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Violation};
+    ///
+    /// let mut machine = Machine::default();
+    /// let requested = Region::new(usize::MAX.into(), 2, 1);
+    ///
+    /// assert_eq!(
+    ///     Err(Violation::RegionOverflow { requested }),
+    ///     machine.push(Alloc(requested))
+    /// );
+    /// ```
+    ///
+    /// Tries to deallocate part of other region:
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Violation};
+    ///
+    /// let mut machine = Machine::default();
+    /// let existing = Region::new(100.into(), 100, 1);
+    ///
+    /// assert!(machine.push(Alloc(existing)).is_ok());
+    ///
+    /// let requested = Region::new(150.into(), 50, 1);
+    /// assert_eq!(
+    ///     Err(Violation::MissingFree { requested }),
+    ///     machine.push(Free(requested))
+    /// );
+    ///
+    /// let requested = Region::new(100.into(), 50, 1);
+    /// assert_eq!(
+    ///     Err(Violation::IncompleteFree { requested, existing }),
+    ///     machine.push(Free(requested))
+    /// );
+    ///
+    /// let requested = Region::new(100.into(), 150, 1);
+    /// assert_eq!(
+    ///     Err(Violation::OversizedFree { requested, existing }),
+    ///     machine.push(Free(requested))
+    /// );
+    /// ```
+    ///
+    /// If a free, or the freed side of a reallocation, starts at an existing
+    /// region but differs from it in more than one way, alignment is checked
+    /// before size. So [Violation::MisalignedFree] takes precedence over
+    /// [Violation::OversizedFree], which in turn takes precedence over
+    /// [Violation::IncompleteFree]:
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Violation};
+    ///
+    /// let mut machine = Machine::default();
+    /// let existing = Region::new(100.into(), 100, 4);
+    ///
+    /// assert!(machine.push(Alloc(existing)).is_ok());
+    ///
+    /// let requested = Region::new(100.into(), 50, 1);
+    /// assert_eq!(
+    ///     Err(Violation::MisalignedFree { requested, existing }),
+    ///     machine.push(Free(requested))
+    /// );
+    /// ```
+    pub fn push(&mut self, mut event: Event) -> Result<(), Violation> {
+        let mut result = self.check(event);
+
+        if let Err(Violation::MissingFree { requested }) = result {
+            if self.is_adoptable(requested) {
+                self.adopted_frees.push(requested);
+
+                // Note: frees of missing regions don't have any effect.
+                if let Event::Realloc(Realloc { free, alloc, .. }) = event {
+                    event = if alloc.size > 0 {
+                        Event::Alloc(alloc)
+                    } else {
+                        Event::Free(free)
+                    };
+                }
+
+                result = match event {
+                    Event::Alloc(..) => self.check(event),
+                    _ => Ok(()),
+                };
+            }
+        }
+
+        match result {
+            // Note: these allocations are still recorded, so that later frees
+            // of them are not reported.
+            Ok(())
+            | Err(Violation::NonZeroedAlloc { .. })
+            | Err(Violation::QuickReuse { .. })
+            | Err(Violation::CrossesPageBoundary { .. }) => self.apply(event),
+            Err(..) => (),
+        }
+
+        self.index += 1;
+
+        if let Some(window) = self.tombstone_window {
+            let index = self.index;
+            self.tombstones.retain(|&(_, at)| index - at <= window);
+        }
+
+        result
+    }
+
+    /// Check if pushing the given event into the machine would raise a
+    /// violation, without modifying the machine.
+    ///
+    /// This performs the same validation as [push][Machine::push], which can
+    /// be used to try candidate regions until one is accepted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Violation};
+    ///
+    /// let mut machine = Machine::default();
+    /// let existing = Region::new(100.into(), 100, 1);
+    /// assert!(machine.push(Alloc(existing)).is_ok());
+    ///
+    /// let requested = Region::new(150.into(), 100, 1);
+    /// assert_eq!(
+    ///     Err(Violation::ConflictingAlloc { requested, existing }),
+    ///     machine.check(Alloc(requested))
+    /// );
+    ///
+    /// assert!(machine.check(Alloc(Region::new(200.into(), 100, 1))).is_ok());
+    /// assert!(machine.check(Free(existing)).is_ok());
+    ///
+    /// // Nothing was modified.
+    /// assert_eq!(100, machine.memory_used());
+    /// assert_eq!(Some(existing), machine.region_at(100.into()));
+    /// assert_eq!(None, machine.region_at(200.into()));
+    /// ```
+    pub fn check(&self, event: Event) -> Result<(), Violation> {
+        match event {
+            Event::Alloc(requested) => self.check_alloc(requested),
+            Event::Free(requested) => self.check_free(requested),
+            Event::FreeByPtr(ptr) => {
+                // Note: without a layout there's nothing to compare against,
+                // so a missing region is reported as a zero-sized request.
+                match self.regions.get(&ptr) {
+                    Some(&existing) => self.check_order(existing),
+                    None => Err(Violation::MissingFree {
+                        requested: Region::new(ptr, 0, 1),
+                    }),
+                }
+            }
+            Event::AllocZeroed(AllocZeroed {
+                is_zeroed,
+                alloc: requested,
+            }) => match self.check_alloc(requested) {
+                // Note: a non-zeroed allocation takes precedence over the
+                // violations which still record the allocation, since it's
+                // the more severe of them.
+                Ok(())
+                | Err(Violation::QuickReuse { .. })
+                | Err(Violation::CrossesPageBoundary { .. })
+                    if is_zeroed == Some(false) =>
+                {
+                    Err(Violation::NonZeroedAlloc { requested })
+                }
+                result => result,
+            },
+            Event::Realloc(Realloc {
+                is_relocated,
+                free,
+                alloc,
+            }) => {
+                if let Some(false) = is_relocated {
+                    return Err(Violation::NonCopiedRealloc { free, alloc });
+                }
+
+                self.check_realloc(free, alloc)
+            }
+            Event::ReallocNull => Err(Violation::ReallocNull {}),
+            Event::BudgetExceeded(BudgetExceeded {
+                requested,
+                memory_used,
+                max_bytes,
+            }) => Err(Violation::BudgetExceeded {
+                requested,
+                memory_used,
+                max_bytes,
+            }),
+            // Note: the following have no effects, outside of what the erorrs
+            // mean to the caller of the allocator. They could for example
+            // decide to gracefully signal OOM (https://github.com/rust-lang/rust/issues/48043)
+            // or panic.
+            Event::AllocFailed => Ok(()),
+            Event::AllocZeroedFailed => Ok(()),
+            Event::ReallocFailed => Ok(()),
+            Event::InjectedFailure(..) => Ok(()),
+        }
+    }
+
+    /// Push a collection of events into the machine, collecting every
+    /// violation encountered instead of stopping at the first one.
+    ///
+    /// Each violation is paired with the index of the event that produced it.
+    /// An allocation which causes a violation is not inserted into the
+    /// machine, but later events are still processed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Violation};
+    ///
+    /// let mut machine = Machine::default();
+    /// let existing = Region::new(100.into(), 100, 1);
+    /// let requested = Region::new(150.into(), 10, 1);
+    ///
+    /// let violations = machine.push_all(vec![
+    ///     Alloc(existing),
+    ///     Alloc(requested),
+    ///     Free(requested),
+    ///     Free(existing),
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         (1, Violation::ConflictingAlloc { requested, existing }),
+    ///         (2, Violation::MissingFree { requested }),
+    ///     ],
+    ///     violations
+    /// );
+    /// ```
+    pub fn push_all<I>(&mut self, events: I) -> Vec<(usize, Violation)>
+    where
+        I: IntoIterator<Item = Event>,
+    {
+        let mut violations = Vec::new();
+
+        for (index, event) in events.into_iter().enumerate() {
+            if let Err(e) = self.push(event) {
+                violations.push((index, e));
+            }
+        }
+
+        violations
+    }
+
+    /// Lazily push events into this machine as they are pulled from the
+    /// returned iterator, which yields the result of each
+    /// [push][Machine::push].
+    ///
+    /// Only the live regions are kept in memory, so this can validate long
+    /// streams of events, like ones decoded from a file, with standard
+    /// iterator combinators. Since the machine is borrowed, it can be inspected
+    /// for leaks once the iterator has been dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Violation};
+    ///
+    /// let a = Region::new(100.into(), 10, 1);
+    /// let b = Region::new(200.into(), 10, 1);
+    /// let events = vec![Alloc(a), Alloc(b), Free(b), Free(b), Free(a)];
+    ///
+    /// let mut machine = Machine::default();
+    /// let first = machine
+    ///     .validate_stream(events.clone())
+    ///     .find_map(Result::err);
+    ///
+    /// // Validation stopped at the double free.
+    /// assert_eq!(Some(Violation::MissingFree { requested: b }), first);
+    /// assert_eq!(4, machine.events_processed());
+    /// assert_eq!(vec![a], machine.trailing_regions());
+    ///
+    /// let mut machine = Machine::default();
+    /// let errors = machine.validate_stream(events).filter(Result::is_err).count();
+    /// assert_eq!(1, errors);
+    /// assert_eq!(0, machine.leaks().count());
+    /// ```
+    pub fn validate_stream<'a, I>(
+        &'a mut self,
+        events: I,
+    ) -> impl Iterator<Item = Result<(), Violation>> + 'a
+    where
+        I: IntoIterator<Item = Event>,
+        I::IntoIter: 'a,
+    {
+        events.into_iter().map(move |event| self.push(event))
+    }
+
+    /// Replay a complete history through this machine, including checking
+    /// for leaked regions once all events have been processed.
+    ///
+    /// Every violation is collected, see [push_all][Machine::push_all]. If
+    /// there are none, the machine is returned so that its state can be
+    /// inspected, otherwise all violations are returned in the order they were
+    /// encountered, followed by any leaks.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Violation};
+    ///
+    /// let a = Region::new(100.into(), 100, 1);
+    /// let b = Region::new(300.into(), 10, 1);
+    ///
+    /// let machine = Machine::default().validate(vec![Alloc(a), Alloc(b), Free(a), Free(b)]);
+    /// assert_eq!(110, machine.unwrap().peak_memory());
+    ///
+    /// let violations = Machine::default()
+    ///     .validate(vec![Alloc(a), Free(b)])
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         Violation::MissingFree { requested: b },
+    ///         Violation::Leaked { region: a },
+    ///     ],
+    ///     violations
+    /// );
+    /// ```
+    pub fn validate<I>(mut self, events: I) -> Result<Machine, Vec<Violation>>
+    where
+        I: IntoIterator<Item = Event>,
+    {
+        let mut violations = self
+            .push_all(events)
+            .into_iter()
+            .map(|(_, e)| e)
+            .collect::<Vec<_>>();
+
+        violations.extend(self.leaks());
+
+        if violations.is_empty() {
+            Ok(self)
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Replay a possibly partial history through this machine, collecting
+    /// every violation together with the index of the event which raised it.
+    ///
+    /// This works like [push_all][Machine::push_all], except that frees and
+    /// reallocations of regions which were never allocated are taken to
+    /// originate from before the start of the history. The missing region is
+    /// reported, then created so that the event can be applied and later
+    /// events stay coherent. Such regions count towards memory use, but not
+    /// towards [total_allocated][Machine::total_allocated].
+    ///
+    /// Regions are not created for pointers which were allocated earlier in
+    /// the history, since freeing them again is a genuine double free. Nor are
+    /// they created for frees by pointer, or for regions conflicting with
+    /// existing ones. Those events are only reported.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Realloc, Violation};
+    ///
+    /// let a = Region::new(100.into(), 10, 1);
+    /// let b = Region::new(200.into(), 20, 1);
+    /// let c = Region::new(300.into(), 40, 1);
+    ///
+    /// let mut machine = Machine::default();
+    ///
+    /// let violations = machine.replay_lenient(vec![
+    ///     Free(a),
+    ///     Realloc(Realloc::new(None, b, c)),
+    ///     Free(c),
+    ///     Free(c),
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         (0, Violation::MissingFree { requested: a }),
+    ///         (1, Violation::MissingFree { requested: b }),
+    ///         (3, Violation::MissingFree { requested: c }),
+    ///     ],
+    ///     violations
+    /// );
+    ///
+    /// assert!(machine.is_clean());
+    /// assert_eq!(40, machine.peak_memory());
+    /// ```
+    pub fn replay_lenient<I>(&mut self, events: I) -> Vec<(usize, Violation)>
+    where
+        I: IntoIterator<Item = Event>,
+    {
+        let mut violations = Vec::new();
+        // Pointers allocated during the replay, frees of which aren't missing
+        // from the history.
+        let mut seen = BTreeSet::new();
+
+        for (index, event) in events.into_iter().enumerate() {
+            if let Err(Violation::MissingFree { requested }) = self.check(event) {
+                if !seen.contains(&requested.ptr) && self.adopt(requested) {
+                    violations.push((index, Violation::MissingFree { requested }));
+                }
+            }
+
+            if let Err(e) = self.push(event) {
+                violations.push((index, e));
+            }
+
+            match event {
+                Event::Alloc(region)
+                | Event::AllocZeroed(AllocZeroed { alloc: region, .. })
+                | Event::Realloc(Realloc { alloc: region, .. }) => {
+                    seen.insert(region.ptr);
+                }
+                _ => (),
+            }
+        }
+
+        violations
+    }
+
+    /// Create a region which was allocated before the history being replayed.
+    /// Returns `false` if the region can't be created.
+    fn adopt(&mut self, requested: Region) -> bool {
+        if requested.size == 0
+            || find_region_overlaps(&self.regions, requested)
+                .next()
+                .is_some()
+        {
+            return false;
+        }
+
+        self.regions.insert(requested.ptr, requested);
+        self.establish(requested.ptr, self.index);
+        self.stack_push(requested.ptr);
+        self.memory_used = self.memory_used.saturating_add(requested.size);
+        self.peak_memory = usize::max(self.memory_used, self.peak_memory);
+        self.peak_region_count = usize::max(self.regions.len(), self.peak_region_count);
+        true
+    }
+
+    /// Test that the alignment of the given region is permitted.
+    fn check_align(&self, requested: Region) -> Result<(), Violation> {
+        match self.max_align {
+            Some(max) if requested.align > max => Err(Violation::ExcessiveAlign { requested, max }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check an allocation.
+    fn check_alloc(&self, requested: Region) -> Result<(), Violation> {
+        validate_region(requested)?;
+        self.check_align(requested)?;
+
+        if requested.size == 0 {
+            return Err(Violation::ZeroSizedAlloc { requested });
+        }
+
+        if !requested.ptr.is_aligned_with(requested.align) {
+            return Err(Violation::MisalignedAlloc { requested });
+        }
+
+        let existing = find_region_overlaps(&self.regions, requested)
+            .next()
+            .or_else(|| self.regions.get(&requested.ptr).copied());
+
+        if let Some(existing) = existing {
+            return Err(Violation::ConflictingAlloc {
+                requested,
+                existing,
+            });
+        }
+
+        if let Some(&(_, freed_at)) = self
+            .tombstones
+            .iter()
+            .rev()
+            .find(|(r, _)| r.overlaps(requested))
+        {
+            return Err(Violation::QuickReuse {
+                requested,
+                freed_at,
+            });
+        }
+
+        self.check_page(requested)
+    }
+
+    /// Check a free.
+    fn check_free(&self, requested: Region) -> Result<(), Violation> {
+        validate_region(requested)?;
+        self.check_align(requested)?;
+        self.check_existing(requested, !self.partial_frees)?;
+        self.check_order(requested)
+    }
+
+    /// Check that `requested` is part of the most recently allocated live
+    /// region, if frees have to be in LIFO order.
+    fn check_order(&self, requested: Region) -> Result<(), Violation> {
+        if self.free_order != FreeOrder::Lifo {
+            return Ok(());
+        }
+
+        let expected = match self.stack.last().and_then(|ptr| self.regions.get(ptr)) {
+            Some(&expected) => expected,
+            None => return Ok(()),
+        };
+
+        if self.region_at(requested.ptr) != Some(expected) {
+            return Err(Violation::OutOfOrderFree {
+                requested,
+                expected,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Check that `requested` can be freed from the region it belongs to.
+    ///
+    /// If `exact` is set the region has to start at the same pointer and have
+    /// the same size, otherwise it's permitted to only cover part of it.
+    fn check_existing(&self, requested: Region, exact: bool) -> Result<(), Violation> {
+        let existing = if exact {
+            self.regions.get(&requested.ptr).copied()
+        } else {
+            self.region_at(requested.ptr)
+        };
+
+        let existing = match existing {
+            Some(existing) => existing,
+            None => return Err(Violation::MissingFree { requested }),
+        };
+
+        // Note: a mismatched alignment takes precedence over a mismatched
+        // size, since it's the more fundamental mistake in the layout.
+        let weaker = self.lenient_free_align && existing.align % requested.align == 0;
+
+        if existing.align != requested.align && !weaker {
+            return Err(Violation::MisalignedFree {
+                requested,
+                existing,
+            });
+        }
+
+        if requested.ptr == existing.ptr && requested.size > existing.size {
+            return Err(Violation::OversizedFree {
+                requested,
+                existing,
+            });
+        }
+
+        let incomplete = if exact {
+            !existing.is_same_region_as(requested)
+        } else {
+            requested.size == 0 || requested.end() > existing.end()
+        };
+
+        if incomplete {
+            return Err(Violation::IncompleteFree {
+                requested,
+                existing,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Check a reallocation.
+    ///
+    /// The region being reallocated is moved as one logical buffer, so it is
+    /// excluded when checking the new region for conflicts.
+    fn check_realloc(&self, free: Region, alloc: Region) -> Result<(), Violation> {
+        validate_region(free)?;
+        validate_region(alloc)?;
+        self.check_align(free)?;
+        self.check_align(alloc)?;
+        self.check_existing(free, true)?;
+
+        if alloc.size == 0 {
+            if self.strict_zero_reallocs {
+                return Err(Violation::ReallocToZero { free });
+            }
+
+            return self.check_order(free);
+        }
+
+        if !alloc.ptr.is_aligned_with(alloc.align) {
+            return Err(Violation::MisalignedAlloc { requested: alloc });
+        }
+
+        if let Some(existing) =
+            find_region_overlaps(&self.regions, alloc).find(|r| r.ptr != free.ptr)
+        {
+            return Err(Violation::ConflictingAlloc {
+                requested: alloc,
+                existing,
+            });
+        }
+
+        self.check_page(alloc)
+    }
+
+    /// Test that an allocated region doesn't needlessly cross a page
+    /// boundary, if the page size is configured.
+    fn check_page(&self, requested: Region) -> Result<(), Violation> {
+        let page_size = match self.page_size {
+            Some(page_size) if page_size > 0 && requested.size <= page_size => page_size,
+            _ => return Ok(()),
+        };
+
+        let first = requested.ptr.0 / page_size;
+        let last = (requested.ptr.0 + requested.size.saturating_sub(1)) / page_size;
+
+        if first != last {
+            return Err(Violation::CrossesPageBoundary {
+                requested,
+                page_size,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Record the index of the event which established the region at `ptr`,
+    /// if enabled.
+    fn establish(&mut self, ptr: Pointer, index: usize) {
+        if self.provenance {
+            self.established.insert(ptr, index);
+        }
+    }
+
+    /// Record that the region at `ptr` is the most recently allocated one, if
+    /// frees have to be in LIFO order.
+    fn stack_push(&mut self, ptr: Pointer) {
+        if self.free_order == FreeOrder::Lifo {
+            self.stack.push(ptr);
+        }
+    }
+
+    /// Remove the region at `ptr` from the allocation order, returning its
+    /// position if it was present.
+    fn stack_remove(&mut self, ptr: Pointer) -> Option<usize> {
+        let at = self.stack.iter().rposition(|&p| p == ptr)?;
+        self.stack.remove(at);
+        Some(at)
+    }
+
+    /// Test if a free of the missing region `requested` is permitted, since
+    /// it was never allocated and doesn't overlap with any live region.
+    fn is_adoptable(&self, requested: Region) -> bool {
+        self.adopt_frees
+            && !self.allocated.contains(&requested.ptr)
+            && find_region_overlaps(&self.regions, requested)
+                .next()
+                .is_none()
+    }
+
+    /// Remember that a region was allocated at `ptr`, if frees of regions
+    /// which were never allocated are permitted.
+    fn remember(&mut self, ptr: Pointer) {
+        if self.adopt_frees {
+            self.allocated.insert(ptr);
+        }
+    }
+
+    /// Count an allocation starting at `ptr`, if enabled.
+    fn occupy(&mut self, ptr: Pointer) {
+        if self.reuse_tracking {
+            let count = self.reuse_counts.entry(ptr).or_default();
+            *count = count.saturating_add(1);
+        }
+    }
+
+    /// Keep a freed region as a tombstone, if enabled.
+    fn bury(&mut self, region: Region) {
+        if self.tombstone_window.is_some() {
+            self.tombstones.push((region, self.index));
+        }
+    }
+
+    /// Apply the effects of an event which has been checked.
+    fn apply(&mut self, event: Event) {
+        match event {
+            Event::Alloc(requested)
+            | Event::AllocZeroed(AllocZeroed {
+                alloc: requested, ..
+            }) => {
+                self.regions.insert(requested.ptr, requested);
+                self.establish(requested.ptr, self.index);
+                self.occupy(requested.ptr);
+                self.remember(requested.ptr);
+                self.stack_push(requested.ptr);
+                self.memory_used = self.memory_used.saturating_add(requested.size);
+                self.total_allocated = self.total_allocated.saturating_add(requested.size);
+            }
+            Event::Free(requested) => {
+                let existing = match self.region_at(requested.ptr) {
+                    Some(existing) => existing,
+                    None => return,
+                };
+
+                self.regions.remove(&existing.ptr);
+                let established = self.established.remove(&existing.ptr);
+                let mut position = self.stack_remove(existing.ptr);
+
+                // Note: unless partial frees are permitted, both of these are
+                // empty.
+                let head = Region {
+                    size: requested.ptr.0 - existing.ptr.0,
+                    ..existing
+                };
+
+                let tail = Region {
+                    ptr: requested.end(),
+                    size: existing.end().0 - requested.end().0,
+                    ..existing
+                };
+
+                for region in [head, tail] {
+                    if region.size > 0 {
+                        self.regions.insert(region.ptr, region);
+
+                        if let Some(established) = established {
+                            self.establish(region.ptr, established);
+                        }
+
+                        // Note: remaining pieces keep the position of the
+                        // region they were part of.
+                        if let Some(at) = position.as_mut() {
+                            self.stack.insert(*at, region.ptr);
+                            *at += 1;
+                        }
+                    }
+                }
+
+                self.memory_used = self.memory_used.saturating_sub(requested.size);
+                self.bury(requested);
+            }
+            Event::FreeByPtr(ptr) => {
+                if let Some(existing) = self.regions.get(&ptr).copied() {
+                    self.apply(Event::Free(existing));
+                }
+            }
+            Event::Realloc(Realloc { free, alloc, .. }) => {
+                if alloc.size == 0 {
+                    self.apply(Event::Free(free));
+                    self.zero_reallocs.push(free);
+                    return;
+                }
+
+                if free.ptr != alloc.ptr {
+                    self.bury(free);
+                    self.occupy(alloc.ptr);
+                    self.remember(alloc.ptr);
+                }
+
+                self.regions.remove(&free.ptr);
+                self.established.remove(&free.ptr);
+
+                match self.stack_remove(free.ptr) {
+                    Some(at) => self.stack.insert(at, alloc.ptr),
+                    None => self.stack_push(alloc.ptr),
+                }
+
+                self.regions.insert(alloc.ptr, alloc);
+                self.establish(alloc.ptr, self.index);
+                self.memory_used = self
+                    .memory_used
+                    .saturating_sub(free.size)
+                    .saturating_add(alloc.size);
+                self.total_allocated = self
+                    .total_allocated
+                    .saturating_add(alloc.size.saturating_sub(free.size));
+            }
+            _ => return,
+        }
+
+        self.peak_memory = usize::max(self.memory_used, self.peak_memory);
+        self.peak_region_count = usize::max(self.regions.len(), self.peak_region_count);
+    }
+
+    /// Get the index of the event which established the live region containing
+    /// `ptr`, if provenance is enabled through
+    /// [with_provenance][Machine::with_provenance].
+    ///
+    /// For reallocations, this is the index of the reallocation. Regions which
+    /// remain after a partial free keep the index of the event which
+    /// established the original region.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Realloc};
+    ///
+    /// let mut machine = Machine::default().with_provenance(true);
+    /// let a = Region::new(100.into(), 100, 1);
+    /// let b = Region::new(300.into(), 100, 1);
+    ///
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    /// assert_eq!(Some(0), machine.established_at(150.into()));
+    ///
+    /// assert!(machine.push(Realloc(Realloc::new(None, a, b))).is_ok());
+    /// assert_eq!(None, machine.established_at(150.into()));
+    /// assert_eq!(Some(1), machine.established_at(300.into()));
+    /// ```
+    pub fn established_at(&self, ptr: Pointer) -> Option<usize> {
+        let region = self.region_at(ptr)?;
+        self.established.get(&region.ptr).copied()
+    }
+
+    /// Find the live region which contains the given pointer, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    /// let region = Region::new(100.into(), 20, 1);
+    ///
+    /// assert!(machine.push(Alloc(region)).is_ok());
+    ///
+    /// assert_eq!(None, machine.region_at(99.into()));
+    /// assert_eq!(Some(region), machine.region_at(100.into()));
+    /// assert_eq!(Some(region), machine.region_at(119.into()));
+    /// assert_eq!(None, machine.region_at(120.into()));
+    /// ```
+    pub fn region_at(&self, ptr: Pointer) -> Option<Region> {
+        let (_, &region) = self.regions.range(..=ptr).next_back()?;
+
+        if region.contains(ptr) {
+            Some(region)
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over all live regions which overlap with `needle`, in ascending
+    /// pointer order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    /// let a = Region::new(100.into(), 50, 1);
+    /// let b = Region::new(150.into(), 50, 1);
+    /// let c = Region::new(300.into(), 50, 1);
+    ///
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    /// assert!(machine.push(Alloc(b)).is_ok());
+    /// assert!(machine.push(Alloc(c)).is_ok());
+    ///
+    /// let needle = Region::new(120.into(), 100, 1);
+    /// assert_eq!(vec![a, b], machine.overlapping(needle).collect::<Vec<_>>());
+    ///
+    /// let needle = Region::new(200.into(), 100, 1);
+    /// assert_eq!(0, machine.overlapping(needle).count());
+    ///
+    /// // A region which extends past the end of the address space is invalid.
+    /// let needle = Region::new(120.into(), usize::MAX, 1);
+    /// assert_eq!(0, machine.overlapping(needle).count());
+    /// ```
+    pub fn overlapping(&self, needle: Region) -> impl Iterator<Item = Region> + '_ {
+        find_region_overlaps(&self.regions, needle)
+    }
+
+    /// Get the size of the free gap between the end of `region` and the start
+    /// of the next live region, or `None` if no live region follows it.
+    ///
+    /// The gap is zero if the next region is adjacent to, or overlaps with,
+    /// `region`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    /// let a = Region::new(100.into(), 20, 1);
+    /// let b = Region::new(150.into(), 10, 1);
+    /// let c = Region::new(160.into(), 10, 1);
+    ///
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    /// assert!(machine.push(Alloc(b)).is_ok());
+    /// assert!(machine.push(Alloc(c)).is_ok());
+    ///
+    /// assert_eq!(Some(30), machine.gap_after(a));
+    /// assert_eq!(Some(0), machine.gap_after(b));
+    /// assert_eq!(None, machine.gap_after(c));
+    /// ```
+    pub fn gap_after(&self, region: Region) -> Option<usize> {
+        let (_, next) = self
+            .regions
+            .range((Bound::Excluded(region.ptr), Bound::Unbounded))
+            .next()?;
+
+        Some(next.ptr.0.saturating_sub(region.end().0))
+    }
+
+    /// Get the size of the free gap between the end of the previous live
+    /// region and the start of `region`, or `None` if no live region precedes
+    /// it.
+    ///
+    /// The gap is zero if the previous region is adjacent to, or overlaps
+    /// with, `region`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    /// let a = Region::new(100.into(), 20, 1);
+    /// let b = Region::new(150.into(), 10, 1);
+    ///
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    /// assert!(machine.push(Alloc(b)).is_ok());
+    ///
+    /// assert_eq!(None, machine.gap_before(a));
+    /// assert_eq!(Some(30), machine.gap_before(b));
+    /// ```
+    pub fn gap_before(&self, region: Region) -> Option<usize> {
+        let (_, prev) = self.regions.range(..region.ptr).next_back()?;
+        Some(region.ptr.0.saturating_sub(prev.end().0))
+    }
+
+    /// The amount of memory currently used according to the events pushed
+    /// into the machine. This is the sum of the sizes of all live regions.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    /// assert!(machine.push(Alloc(Region::new(10.into(), 10, 1))).is_ok());
+    /// assert!(machine.push(Alloc(Region::new(20.into(), 30, 1))).is_ok());
+    /// assert!(machine.push(Free(Region::new(10.into(), 10, 1))).is_ok());
+    ///
+    /// assert_eq!(30, machine.memory_used());
+    /// ```
+    pub fn memory_used(&self) -> usize {
+        self.memory_used
+    }
+
+    /// The highest amount of memory used at any point according to the events
+    /// pushed into the machine.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Realloc};
+    ///
+    /// let mut machine = Machine::default();
+    /// let a = Region::new(10.into(), 10, 1);
+    /// let b = Region::new(100.into(), 40, 1);
+    ///
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    /// assert!(machine.push(Realloc(Realloc::new(None, a, b))).is_ok());
+    /// assert!(machine.push(Free(b)).is_ok());
+    ///
+    /// assert_eq!(0, machine.memory_used());
+    /// assert_eq!(40, machine.peak_memory());
+    /// ```
+    pub fn peak_memory(&self) -> usize {
+        self.peak_memory
+    }
+
+    /// The highest number of regions live at the same time according to the
+    /// events pushed into the machine.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    /// let a = Region::new(10.into(), 10, 1);
+    /// let b = Region::new(20.into(), 10, 1);
+    ///
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    /// assert!(machine.push(Alloc(b)).is_ok());
+    /// assert!(machine.push(Free(a)).is_ok());
+    /// assert!(machine.push(Free(b)).is_ok());
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    ///
+    /// assert_eq!(1, machine.live_region_count());
+    /// assert_eq!(2, machine.peak_region_count());
+    /// ```
+    pub fn peak_region_count(&self) -> usize {
+        self.peak_region_count
+    }
+
+    /// The total number of bytes ever allocated according to the events pushed
+    /// into the machine.
+    ///
+    /// Unlike [memory_used][Machine::memory_used] this never decreases. A
+    /// reallocation only counts the number of bytes it grew by.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Realloc};
+    ///
+    /// let mut machine = Machine::default();
+    /// let a = Region::new(10.into(), 10, 1);
+    /// let b = Region::new(100.into(), 40, 1);
+    ///
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    /// assert!(machine.push(Realloc(Realloc::new(None, a, b))).is_ok());
+    /// assert!(machine.push(Free(b)).is_ok());
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    ///
+    /// assert_eq!(10, machine.memory_used());
+    /// assert_eq!(50, machine.total_allocated());
+    /// ```
+    pub fn total_allocated(&self) -> usize {
+        self.total_allocated
+    }
+
+    /// The number of events pushed into the machine, including the ones which
+    /// raised a violation.
+    ///
+    /// Events checked with [check][Machine::check] are not counted, and the
+    /// count is reset by [clear][Machine::clear].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    /// let region = Region::new(10.into(), 10, 1);
+    ///
+    /// assert!(machine.push(Alloc(region)).is_ok());
+    /// assert!(machine.check(Free(region)).is_ok());
+    /// assert!(machine.push(Alloc(region)).is_err());
+    ///
+    /// assert_eq!(2, machine.events_processed());
+    /// ```
+    pub fn events_processed(&self) -> usize {
+        self.index
+    }
+
+    /// Access the number of allocations which have started at each address,
+    /// if counting is enabled through
+    /// [with_reuse_counts][Machine::with_reuse_counts].
+    ///
+    /// Counts are kept after regions are freed, so an address with a count
+    /// above one has been reused. Reallocations in place don't count as a new
+    /// allocation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default().with_reuse_counts(true);
+    /// let region = Region::new(100.into(), 10, 1);
+    ///
+    /// for _ in 0..3 {
+    ///     assert!(machine.push(Alloc(region)).is_ok());
+    ///     assert!(machine.push(Free(region)).is_ok());
+    /// }
+    ///
+    /// let reused = machine.reuse_counts().values().filter(|&&n| n > 1).count();
+    /// assert_eq!(1, reused);
+    /// assert_eq!(Some(&3), machine.reuse_counts().get(&region.ptr));
+    /// ```
+    pub fn reuse_counts(&self) -> &BTreeMap<Pointer, usize> {
+        &self.reuse_counts
+    }
+
+    /// Access the frees of regions which were never allocated, if they are
+    /// permitted through [with_adopted_frees][Machine::with_adopted_frees].
+    ///
+    /// Frees by pointer are represented by a zero-sized region, and
+    /// reallocations by the region they reallocated.
+    pub fn adopted_frees(&self) -> &[Region] {
+        &self.adopted_frees
+    }
+
+    /// Access the regions which were freed through a reallocation to a size
+    /// of zero, see [with_strict_zero_reallocs][Machine::with_strict_zero_reallocs].
+    pub fn zero_reallocs(&self) -> &[Region] {
+        &self.zero_reallocs
+    }
+
+    /// Measure the fragmentation of the live regions, by walking them in
+    /// ascending pointer order and measuring the gaps between them.
+    ///
+    /// Dividing [Fragmentation::total_gap] by [memory_used][Machine::memory_used]
+    /// gives the amount of unused space per live byte in the span of memory
+    /// being used.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    ///
+    /// assert!(machine.push(Alloc(Region::new(100.into(), 10, 1))).is_ok());
+    /// assert!(machine.push(Alloc(Region::new(110.into(), 10, 1))).is_ok());
+    /// assert!(machine.push(Alloc(Region::new(130.into(), 10, 1))).is_ok());
+    /// assert!(machine.push(Alloc(Region::new(200.into(), 10, 1))).is_ok());
+    ///
+    /// let fragmentation = machine.fragmentation();
+    /// assert_eq!(60, fragmentation.largest_gap);
+    /// assert_eq!(70, fragmentation.total_gap);
+    /// assert_eq!(2, fragmentation.gap_count);
+    /// ```
+    pub fn fragmentation(&self) -> Fragmentation {
+        let mut fragmentation = Fragmentation::default();
+        let mut regions = self.regions();
+
+        let mut end = match regions.next() {
+            Some(region) => region.end(),
+            None => return fragmentation,
+        };
+
+        for region in regions {
+            let gap = region.ptr.0.saturating_sub(end.0);
+
+            if gap > 0 {
+                fragmentation.largest_gap = usize::max(fragmentation.largest_gap, gap);
+                fragmentation.total_gap += gap;
+                fragmentation.gap_count += 1;
+            }
+
+            end = region.end();
         }
 
-        let (_, region) = entry.remove_entry();
-        self.memory_used = self.memory_used.saturating_sub(region.size);
-        Ok(())
+        fragmentation
+    }
+
+    /// Iterate over all live regions in ascending pointer order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    ///
+    /// assert!(machine.push(Alloc(Region::new(20.into(), 30, 1))).is_ok());
+    /// assert!(machine.push(Alloc(Region::new(10.into(), 10, 1))).is_ok());
+    ///
+    /// assert_eq!(2, machine.regions().count());
+    /// assert_eq!(40, machine.regions().map(|r| r.size).sum::<usize>());
+    /// assert_eq!(Some(Region::new(10.into(), 10, 1)), machine.regions().next());
+    /// ```
+    pub fn regions(&self) -> impl Iterator<Item = Region> + '_ {
+        self.regions.values().copied()
+    }
+
+    /// Borrow the map of live regions, keyed by their pointer.
+    ///
+    /// This can be compared directly against the live set of another model of
+    /// an allocator, without collecting the regions first.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    ///
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    /// let a = Region::new(20.into(), 30, 1);
+    /// let b = Region::new(10.into(), 10, 1);
+    ///
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    /// assert!(machine.push(Alloc(b)).is_ok());
+    ///
+    /// let mut expected = BTreeMap::new();
+    /// expected.insert(a.ptr, a);
+    /// expected.insert(b.ptr, b);
+    ///
+    /// assert_eq!(&expected, machine.regions_map());
+    /// ```
+    pub fn regions_map(&self) -> &BTreeMap<Pointer, Region> {
+        &self.regions
+    }
+
+    /// Get the number of live regions.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    /// assert_eq!(0, machine.live_region_count());
+    ///
+    /// assert!(machine.push(Alloc(Region::new(10.into(), 10, 1))).is_ok());
+    /// assert!(machine.push(Alloc(Region::new(20.into(), 30, 1))).is_ok());
+    /// assert!(machine.push(Free(Region::new(10.into(), 10, 1))).is_ok());
+    ///
+    /// assert_eq!(1, machine.live_region_count());
+    /// ```
+    pub fn live_region_count(&self) -> usize {
+        self.regions.len()
+    }
+
+    /// Test if the machine is leak-free, that is it has no live regions and no
+    /// memory in use.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    /// assert!(machine.is_clean());
+    ///
+    /// let region = Region::new(10.into(), 10, 1);
+    /// assert!(machine.push(Alloc(region)).is_ok());
+    /// assert!(!machine.is_clean());
+    ///
+    /// assert!(machine.push(Free(region)).is_ok());
+    /// assert!(machine.is_clean());
+    /// ```
+    pub fn is_clean(&self) -> bool {
+        self.regions.is_empty() && self.memory_used == 0
+    }
+
+    /// Count the live regions in each power-of-two size class.
+    ///
+    /// A region belongs to the class `n` if its size rounded up to the next
+    /// power of two is `2^n`, so the class `4` counts regions with sizes in
+    /// `(8, 16]`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    /// assert!(machine.push(Alloc(Region::new(0.into(), 16, 1))).is_ok());
+    /// assert!(machine.push(Alloc(Region::new(16.into(), 12, 1))).is_ok());
+    /// assert!(machine.push(Alloc(Region::new(32.into(), 17, 1))).is_ok());
+    /// assert!(machine.push(Alloc(Region::new(64.into(), 1, 1))).is_ok());
+    ///
+    /// let classes = machine.live_by_size_class();
+    /// assert_eq!(Some(&1), classes.get(&0));
+    /// assert_eq!(Some(&2), classes.get(&4));
+    /// assert_eq!(Some(&1), classes.get(&5));
+    /// assert_eq!(3, classes.len());
+    /// ```
+    pub fn live_by_size_class(&self) -> BTreeMap<u32, usize> {
+        let mut classes = BTreeMap::new();
+
+        for region in self.regions.values() {
+            // Note: sizes too large to round up belong to the class past the
+            // largest power of two.
+            let class = region
+                .size
+                .checked_next_power_of_two()
+                .map_or(usize::BITS, usize::trailing_zeros);
+
+            *classes.entry(class).or_default() += 1;
+        }
+
+        classes
+    }
+
+    /// Get the largest live region, if any. If several regions have the
+    /// largest size, the one with the lowest address is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    /// assert_eq!(None, machine.largest_region());
+    ///
+    /// assert!(machine.push(Alloc(Region::new(10.into(), 10, 1))).is_ok());
+    /// assert!(machine.push(Alloc(Region::new(20.into(), 30, 1))).is_ok());
+    /// assert!(machine.push(Alloc(Region::new(50.into(), 30, 1))).is_ok());
+    ///
+    /// assert_eq!(Some(Region::new(20.into(), 30, 1)), machine.largest_region());
+    /// ```
+    pub fn largest_region(&self) -> Option<Region> {
+        self.regions()
+            .max_by(|a, b| a.size.cmp(&b.size).then(b.ptr.cmp(&a.ptr)))
+    }
+
+    /// Get up to `n` of the largest live regions, sorted by descending size.
+    /// Regions of the same size are ordered by ascending address, like in
+    /// [largest_region][Machine::largest_region].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    /// let a = Region::new(10.into(), 10, 1);
+    /// let b = Region::new(20.into(), 30, 1);
+    /// let c = Region::new(50.into(), 5, 1);
+    /// let d = Region::new(60.into(), 30, 1);
+    ///
+    /// for region in [a, b, c, d] {
+    ///     assert!(machine.push(Alloc(region)).is_ok());
+    /// }
+    ///
+    /// assert_eq!(vec![b, d, a], machine.largest_leaks(3));
+    /// assert_eq!(vec![b, d, a, c], machine.largest_leaks(10));
+    /// assert!(machine.largest_leaks(0).is_empty());
+    /// ```
+    pub fn largest_leaks(&self, n: usize) -> Vec<Region> {
+        if n == 0 {
+            return Vec::new();
+        }
+
+        // Note: a min-heap of the largest regions seen so far, so that the
+        // smallest of them can be evicted.
+        let mut heap = BinaryHeap::with_capacity(usize::min(n, self.regions.len()) + 1);
+
+        for &region in self.regions.values() {
+            heap.push(Reverse((region.size, Reverse(region.ptr), region)));
+
+            if heap.len() > n {
+                heap.pop();
+            }
+        }
+
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((_, _, region))| region)
+            .collect()
     }
 
     /// Access all trailing regions (ones which have not been deallocated).
     pub fn trailing_regions(&self) -> Vec<Region> {
-        self.regions.values().copied().collect()
+        self.regions().collect()
+    }
+
+    /// Test if the live regions are exactly the `expected` regions, in any
+    /// order. Regions are compared by pointer, size and alignment.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    /// let a = Region::new(10.into(), 10, 1);
+    /// let b = Region::new(20.into(), 10, 1);
+    ///
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    /// assert!(machine.push(Alloc(b)).is_ok());
+    ///
+    /// assert!(machine.regions_match(&[b, a]));
+    /// assert!(!machine.regions_match(&[a]));
+    /// assert!(!machine.regions_match(&[a, Region::new(20.into(), 10, 2)]));
+    /// ```
+    pub fn regions_match(&self, expected: &[Region]) -> bool {
+        let diff = self.diff_regions(expected);
+        diff.unexpected.is_empty() && diff.missing.is_empty()
+    }
+
+    /// Assert that the live regions are exactly the `expected` regions, in
+    /// any order, see [regions_match][Machine::regions_match].
+    ///
+    /// # Panics
+    ///
+    /// Panics with a report of the unexpected live regions, marked with `+`,
+    /// and the missing expected regions, marked with `-`, if they don't match.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine};
+    ///
+    /// let mut machine = Machine::default();
+    /// let a = Region::new(0x10.into(), 16, 1);
+    /// let b = Region::new(0x20.into(), 16, 1);
+    ///
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    /// assert!(machine.push(Alloc(b)).is_ok());
+    /// machine.assert_regions(&[a, b]);
+    ///
+    /// let c = Region::new(0x20.into(), 8, 1);
+    /// let result = std::panic::catch_unwind(|| machine.assert_regions(&[a, c]));
+    /// let message = *result.unwrap_err().downcast::<String>().unwrap();
+    ///
+    /// assert_eq!(
+    ///     "live regions don't match the expected regions:\n  \
+    ///      + 0x20-0x30 (size: 16 B, align: 1)\n  \
+    ///      - 0x20-0x28 (size: 8 B, align: 1)",
+    ///     message
+    /// );
+    /// ```
+    pub fn assert_regions(&self, expected: &[Region]) {
+        let diff = self.diff_regions(expected);
+
+        if !diff.unexpected.is_empty() || !diff.missing.is_empty() {
+            panic!("live regions don't match the expected regions:{}", diff);
+        }
+    }
+
+    /// Compare the live regions against the `expected` regions.
+    fn diff_regions(&self, expected: &[Region]) -> RegionsDiff {
+        let mut expected = expected.to_vec();
+        expected.sort();
+
+        let mut diff = RegionsDiff {
+            unexpected: Vec::new(),
+            missing: Vec::new(),
+        };
+
+        let mut live = self.regions().peekable();
+        let mut expected = expected.into_iter().peekable();
+
+        // Note: both sides are sorted, so they can be walked in step.
+        loop {
+            match (live.peek(), expected.peek()) {
+                (Some(a), Some(b)) if a == b => {
+                    live.next();
+                    expected.next();
+                }
+                (Some(a), Some(b)) if a < b => diff.unexpected.extend(live.next()),
+                (_, Some(_)) => diff.missing.extend(expected.next()),
+                (Some(_), None) => diff.unexpected.extend(live.next()),
+                (None, None) => break,
+            }
+        }
+
+        diff
+    }
+
+    /// Access a [Violation::Leaked] for every trailing region (ones which have
+    /// not been deallocated), in ascending pointer order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Event::*, Region, Machine, Violation};
+    ///
+    /// let mut machine = Machine::default();
+    /// let a = Region::new(20.into(), 10, 1);
+    /// let b = Region::new(10.into(), 10, 1);
+    ///
+    /// assert!(machine.push(Alloc(a)).is_ok());
+    /// assert!(machine.push(Alloc(b)).is_ok());
+    ///
+    /// let leaks = machine.leaks().collect::<Vec<_>>();
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         Violation::Leaked { region: b },
+    ///         Violation::Leaked { region: a },
+    ///     ],
+    ///     leaks
+    /// );
+    /// ```
+    pub fn leaks(&self) -> impl Iterator<Item = Violation> + '_ {
+        self.regions().map(|region| Violation::Leaked { region })
+    }
+}
+
+/// The difference between the live regions of a machine and the regions
+/// expected by [Machine::assert_regions].
+struct RegionsDiff {
+    /// Live regions which were not expected.
+    unexpected: Vec<Region>,
+    /// Expected regions which are not live.
+    missing: Vec<Region>,
+}
+
+impl fmt::Display for RegionsDiff {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for region in &self.unexpected {
+            write!(fmt, "\n  + {}", region)?;
+        }
+
+        for region in &self.missing {
+            write!(fmt, "\n  - {}", region)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Validate the invariants of a region which don't depend on the state of the
+/// machine.
+fn validate_region(requested: Region) -> Result<(), Violation> {
+    if !requested.align.is_power_of_two() {
+        return Err(Violation::InvalidAlign { requested });
     }
+
+    if requested.ptr.checked_add(requested.size).is_none() {
+        return Err(Violation::RegionOverflow { requested });
+    }
+
+    Ok(())
 }
 
-/// Utility function to find overlapping regions.
+/// Utility function to find overlapping regions, in ascending pointer order.
+///
+/// A needle which extends past the end of the address space is invalid and
+/// overlaps nothing, since a saturated end would make it appear to span every
+/// region above it.
 fn find_region_overlaps<'a>(
     regions: &'a BTreeMap<Pointer, Region>,
     needle: Region,
 ) -> impl Iterator<Item = Region> + 'a {
+    let end = needle.ptr.checked_add(needle.size);
+
+    // Note: regions are disjoint, so only the closest region starting before
+    // `needle.ptr` can overlap with it.
     let head = regions
-        .range(..=needle.ptr)
-        .take_while(move |(_, &r)| r.overlaps(needle));
+        .range(..needle.ptr)
+        .next_back()
+        .map(|(_, &r)| r)
+        .filter(move |r| end.is_some() && r.overlaps(needle));
 
     let tail = regions
         .range(needle.ptr..)
-        .take_while(move |(_, &r)| r.overlaps(needle));
+        .map(|(_, &r)| r)
+        .take_while(move |r| matches!(end, Some(end) if r.ptr < end))
+        .filter(move |r| r.overlaps(needle));
 
-    head.chain(tail).map(|(_, &r)| r)
+    head.into_iter().chain(tail)
 }
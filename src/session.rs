@@ -0,0 +1,213 @@
+//! Scoped capture driven by a guard, see [begin].
+//!
+//! [begin]: crate::begin
+
+use std::marker::PhantomData;
+#[cfg(feature = "timestamps")]
+use std::time::Duration;
+
+use crate::{MuteGuard, Snapshot};
+
+/// What a [Session] does with its snapshot when it's dropped without being
+/// [finished][Session::finish].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DropPolicy {
+    /// Discard the snapshot.
+    #[default]
+    Silent,
+    /// Panic if any region allocated during the session is still live, see
+    /// [Snapshot::assert_no_leaks]. Nothing is checked if the thread is
+    /// already panicking.
+    PanicOnLeak,
+}
+
+/// Guard which captures every allocation performed by the current thread
+/// while it's alive, as returned by [begin].
+///
+/// This is the guard-based counterpart to [with], for when the code being
+/// checked doesn't fit in a closure. Sessions nest like calls to [with], and
+/// need to be dropped in the reverse order of how they were started.
+///
+/// Since capture is thread-local, a session is `!Send` and can't be moved to
+/// another thread. Allocations performed by other threads aren't captured,
+/// see [with_threads] for that.
+///
+/// [begin]: crate::begin
+/// [with]: crate::with
+/// [with_threads]: crate::with_threads
+#[must_use = "capture ends as soon as the session is dropped"]
+pub struct Session {
+    start: usize,
+    used: usize,
+    peak: usize,
+    skipped: usize,
+    #[cfg(feature = "timestamps")]
+    clock: Duration,
+    policy: DropPolicy,
+    finished: bool,
+    _unmuted: MuteGuard,
+    // Note: capture is thread-local, so the session must not leave the
+    // thread which started it.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Session {
+    /// Start a new session on the current thread.
+    pub(crate) fn begin() -> Self {
+        crate::with_state(|s| {
+            let mut s = s.borrow_mut();
+
+            if s.depth == 0 {
+                s.clear();
+                #[cfg(feature = "timestamps")]
+                s.events.start_clock();
+            }
+
+            s.depth += 1;
+            let peak = s.peak_memory;
+            s.peak_memory = s.memory_used;
+
+            Self {
+                start: s.events.len(),
+                used: s.memory_used,
+                peak,
+                skipped: s.skipped_allocs,
+                #[cfg(feature = "timestamps")]
+                clock: s.events.elapsed(),
+                policy: DropPolicy::Silent,
+                finished: false,
+                _unmuted: crate::mute_guard(false),
+                _not_send: PhantomData,
+            }
+        })
+    }
+
+    /// Set what happens when the session is dropped without being finished.
+    /// Defaults to [DropPolicy::Silent].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,should_panic
+    /// use checkers::DropPolicy;
+    ///
+    /// #[global_allocator]
+    /// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+    ///
+    /// let _session = checkers::begin().with_drop_policy(DropPolicy::PanicOnLeak);
+    /// std::mem::forget(vec![1, 2, 3, 4]);
+    /// ```
+    pub fn with_drop_policy(mut self, policy: DropPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Get a snapshot of the events captured so far, without ending the
+    /// session.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #[global_allocator]
+    /// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+    ///
+    /// let session = checkers::begin();
+    /// let a = Box::new(1);
+    ///
+    /// let snapshot = session.snapshot();
+    /// assert_eq!(1, snapshot.events.allocs());
+    /// assert_eq!(0, snapshot.events.frees());
+    ///
+    /// drop(a);
+    /// assert_eq!(1, session.finish().events.frees());
+    /// ```
+    pub fn snapshot(&self) -> Snapshot {
+        // Note: building the snapshot allocates, which we don't want to be
+        // tracked.
+        let _g = crate::mute_guard(true);
+
+        crate::with_state(|s| {
+            let s = s.borrow();
+
+            let events = s.events.tail(self.start);
+
+            #[cfg(feature = "timestamps")]
+            let events = events.rebased(self.clock);
+
+            Snapshot {
+                events,
+                #[cfg(feature = "backtrace")]
+                backtraces: s
+                    .backtraces
+                    .range(self.start..)
+                    .map(|(index, backtrace)| (index - self.start, backtrace.clone()))
+                    .collect(),
+                peak_memory: s.peak_memory.saturating_sub(self.used),
+                max_bytes: s.max_bytes,
+                skipped_allocs: s.skipped_allocs.saturating_sub(self.skipped),
+            }
+        })
+    }
+
+    /// End the session and get a snapshot of every event it captured. The
+    /// drop policy doesn't apply to a finished session.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #[global_allocator]
+    /// static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+    ///
+    /// let session = checkers::begin();
+    /// let _ = vec![1, 2, 3, 4];
+    /// let snapshot = session.finish();
+    ///
+    /// assert_eq!(1, snapshot.events.allocs());
+    /// assert_eq!(1, snapshot.events.frees());
+    /// ```
+    pub fn finish(mut self) -> Snapshot {
+        self.finished = true;
+        self.end()
+    }
+
+    /// Leave the scope opened by the session and build its snapshot.
+    fn end(&mut self) -> Snapshot {
+        let _g = crate::mute_guard(true);
+
+        crate::with_state(|s| {
+            let mut s = s.borrow_mut();
+            s.depth = s.depth.saturating_sub(1);
+        });
+
+        let snapshot = self.snapshot();
+
+        crate::with_state(|s| {
+            let mut s = s.borrow_mut();
+            s.peak_memory = usize::max(self.peak, s.peak_memory);
+        });
+
+        snapshot
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        // Note: the snapshot is dropped here, and its frees shouldn't be
+        // tracked by an enclosing scope.
+        let _g = crate::mute_guard(true);
+        let snapshot = self.end();
+
+        match self.policy {
+            DropPolicy::Silent => (),
+            DropPolicy::PanicOnLeak => {
+                if !std::thread::panicking() {
+                    snapshot.assert_no_leaks();
+                }
+            }
+        }
+    }
+}
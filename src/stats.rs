@@ -0,0 +1,54 @@
+//! Summary statistics of a snapshot.
+
+use crate::{Machine, Snapshot};
+
+/// Summary statistics of a snapshot, as produced by [Snapshot::stats].
+///
+/// Leaks are the regions which are still live at the end of the snapshot.
+/// Unlike most types in this crate this isn't marked as non-exhaustive, so
+/// that a whole literal can be compared against in an assertion.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of allocations, see [Events::allocs].
+    ///
+    /// [Events::allocs]: crate::Events::allocs
+    pub allocs: usize,
+    /// The number of frees, see [Events::frees].
+    ///
+    /// [Events::frees]: crate::Events::frees
+    pub frees: usize,
+    /// The number of reallocations, see [Events::reallocs].
+    ///
+    /// [Events::reallocs]: crate::Events::reallocs
+    pub reallocs: usize,
+    /// The highest number of bytes live at once, see [Snapshot::peak_memory].
+    pub peak_memory: usize,
+    /// The number of bytes live at the end of the snapshot.
+    pub current_memory: usize,
+    /// The total number of bytes allocated, see [Events::total_allocated].
+    ///
+    /// [Events::total_allocated]: crate::Events::total_allocated
+    pub total_allocated: usize,
+    /// The number of regions leaked.
+    pub leak_count: usize,
+    /// The number of bytes leaked.
+    pub leak_bytes: usize,
+}
+
+impl Stats {
+    pub(crate) fn new(snapshot: &Snapshot) -> Self {
+        let mut machine = Machine::default();
+        let _ = machine.push_all(snapshot.events.iter().copied());
+
+        Self {
+            allocs: snapshot.events.allocs(),
+            frees: snapshot.events.frees(),
+            reallocs: snapshot.events.reallocs(),
+            peak_memory: snapshot.peak_memory,
+            current_memory: machine.memory_used(),
+            total_allocated: snapshot.events.total_allocated(),
+            leak_count: machine.live_region_count(),
+            leak_bytes: machine.regions().map(|r| r.size).sum(),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! [proptest] strategies for generating allocation histories.
+
+use alloc::vec::Vec;
+use proptest::{collection, strategy::Strategy};
+
+use crate::{Event, Pointer, Region};
+
+/// The address at which generated regions start being placed.
+const BASE: usize = 0x1000;
+
+/// The largest alignment of generated regions, as a power of two.
+const MAX_ALIGN_POW: u32 = 4;
+
+/// Construct a [Strategy] which generates valid allocation histories of at
+/// most `max_regions` regions, each with a size of at most `max_size` bytes.
+///
+/// Every region is allocated once and freed once after it has been allocated,
+/// and regions never overlap. So replaying a generated history through a
+/// [Machine] produces no violations, unless it is mutated first. Generated
+/// pointers stay within a bounded range above a small non-zero base address.
+///
+/// Shrinking removes regions from the history, which keeps it valid.
+///
+/// [Machine]: crate::Machine
+///
+/// # Examples
+///
+/// ```rust
+/// use checkers::{Event, Machine};
+/// use proptest::{strategy::{Strategy, ValueTree}, test_runner::TestRunner};
+///
+/// let mut runner = TestRunner::default();
+/// let events = checkers::event_strategy(16, 1024)
+///     .new_tree(&mut runner)
+///     .unwrap()
+///     .current();
+///
+/// let mut machine = Machine::default();
+/// assert!(machine.push_all(events).is_empty());
+/// assert_eq!(0, machine.memory_used());
+/// ```
+pub fn event_strategy(max_regions: usize, max_size: usize) -> impl Strategy<Value = Vec<Event>> {
+    let region = (
+        1..=usize::max(max_size, 1),
+        0..=MAX_ALIGN_POW,
+        proptest::num::u32::ANY,
+        proptest::num::u32::ANY,
+    );
+
+    collection::vec(region, 0..=max_regions).prop_map(|specs| {
+        let mut ptr = BASE;
+        let mut keyed = Vec::with_capacity(specs.len() * 2);
+
+        for (index, (size, align_pow, a, b)) in specs.into_iter().enumerate() {
+            let align = 1 << align_pow;
+            ptr = (ptr + align - 1) & !(align - 1);
+
+            let region = Region::new(Pointer::from(ptr), size, align);
+            ptr += size;
+
+            // Note: the alloc of a region sorts before its free, even if both
+            // keys are the same.
+            keyed.push(((u32::min(a, b), 0, index), Event::Alloc(region)));
+            keyed.push(((u32::max(a, b), 1, index), Event::Free(region)));
+        }
+
+        keyed.sort_by_key(|&(key, _)| key);
+        keyed.into_iter().map(|(_, event)| event).collect()
+    })
+}
@@ -15,7 +15,37 @@ pub(crate) unsafe fn is_zeroed_ptr(ptr: *const u8, len: usize) -> bool {
 ///
 /// `ptr` needs to be non-null and initialized to the length specified in `len`.
 #[cfg(feature = "realloc")]
-pub(crate) unsafe fn hash_ptr(ptr: *const u8, len: usize) -> impl PartialEq + Eq {
+pub(crate) unsafe fn hash_ptr(ptr: *const u8, len: usize) -> impl Eq {
     debug_assert!(!ptr.is_null());
     fxhash::hash64(std::slice::from_raw_parts(ptr, len))
 }
+
+/// Helper to format a number of bytes in a human-readable way using binary
+/// units, like `512 B`, `16.0 KiB` or `1.00 MiB`.
+pub(crate) struct Bytes(pub(crate) usize);
+
+impl core::fmt::Display for Bytes {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+        if self.0 < 1024 {
+            return write!(fmt, "{} B", self.0);
+        }
+
+        let mut value = self.0 as f64 / 1024.0;
+        let mut unit = 0;
+
+        while value >= 1024.0 && unit + 1 < UNITS.len() {
+            value /= 1024.0;
+            unit += 1;
+        }
+
+        if value < 10.0 {
+            write!(fmt, "{:.2} {}", value, UNITS[unit])
+        } else if value < 100.0 {
+            write!(fmt, "{:.1} {}", value, UNITS[unit])
+        } else {
+            write!(fmt, "{:.0} {}", value, UNITS[unit])
+        }
+    }
+}
@@ -1,9 +1,96 @@
-use std::fmt;
+use alloc::collections::BTreeMap;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error;
 
-use crate::Region;
+use crate::{utils::Bytes, Region};
+
+/// The severity of a [Violation], ordered from least to most severe.
+///
+/// See [Violation::severity] for how violations are classified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    /// A cleanup issue which doesn't affect the soundness of the program, like
+    /// a leak.
+    Warning,
+    /// The allocator or the program broke the allocation contract, or a
+    /// configured limit was exceeded, but memory was not corrupted.
+    Error,
+    /// Memory was or is likely to be corrupted, like a double-free or two
+    /// allocations sharing memory.
+    Critical,
+}
+
+/// A stable numeric code for each kind of [Violation], see
+/// [Violation::code].
+///
+/// Codes are never reused or changed between versions, and new codes are only
+/// ever added at the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+#[repr(u16)]
+pub enum ViolationCode {
+    /// See [Violation::ConflictingAlloc].
+    ConflictingAlloc = 1,
+    /// See [Violation::NonZeroedAlloc].
+    NonZeroedAlloc = 2,
+    /// See [Violation::NonCopiedRealloc].
+    NonCopiedRealloc = 3,
+    /// See [Violation::ReallocNull].
+    ReallocNull = 4,
+    /// See [Violation::MisalignedAlloc].
+    MisalignedAlloc = 5,
+    /// See [Violation::InvalidAlign].
+    InvalidAlign = 6,
+    /// See [Violation::ExcessiveAlign].
+    ExcessiveAlign = 7,
+    /// See [Violation::RegionOverflow].
+    RegionOverflow = 8,
+    /// See [Violation::ZeroSizedAlloc].
+    ZeroSizedAlloc = 9,
+    /// See [Violation::IncompleteFree].
+    IncompleteFree = 10,
+    /// See [Violation::OversizedFree].
+    OversizedFree = 11,
+    /// See [Violation::MisalignedFree].
+    MisalignedFree = 12,
+    /// See [Violation::MissingFree].
+    MissingFree = 13,
+    /// See [Violation::BudgetExceeded].
+    BudgetExceeded = 14,
+    /// See [Violation::QuickReuse].
+    QuickReuse = 15,
+    /// See [Violation::OutOfOrderFree].
+    OutOfOrderFree = 16,
+    /// See [Violation::Leaked].
+    Leaked = 17,
+    /// See [Violation::CrossesPageBoundary].
+    CrossesPageBoundary = 18,
+    /// See [Violation::ReallocToZero].
+    ReallocToZero = 19,
+}
+
+impl ViolationCode {
+    /// Get the numeric value of the code.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::ViolationCode;
+    ///
+    /// assert_eq!(1, ViolationCode::ConflictingAlloc.value());
+    /// assert_eq!(17, ViolationCode::Leaked.value());
+    /// ```
+    pub fn value(self) -> u16 {
+        self as u16
+    }
+}
 
 /// A single violation in the variants enforced by checkers.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Violation {
     /// A region produced by the allocator `requested`, overlaps with at least
     /// on `existing` allocation.
@@ -35,6 +122,33 @@ pub enum Violation {
         /// The allocated region.
         requested: Region,
     },
+    /// A region `requested` has an alignment which is not a power of two.
+    /// See [std::alloc::Layout::from_size_align].
+    InvalidAlign {
+        /// The requested region.
+        requested: Region,
+    },
+    /// A region `requested` has an alignment larger than the `max` alignment
+    /// permitted. See [Machine::with_max_align].
+    ///
+    /// [Machine::with_max_align]: crate::Machine::with_max_align
+    ExcessiveAlign {
+        /// The requested region.
+        requested: Region,
+        /// The largest alignment permitted.
+        max: usize,
+    },
+    /// A region `requested` extends past the end of the address space.
+    RegionOverflow {
+        /// The requested region.
+        requested: Region,
+    },
+    /// A region produced by the allocator `requested` has a size of zero,
+    /// which is not permitted by [std::alloc::GlobalAlloc::alloc].
+    ZeroSizedAlloc {
+        /// The allocated region.
+        requested: Region,
+    },
     /// A freed region `requested` only freed part of at least one other region
     /// `existing`.
     IncompleteFree {
@@ -43,6 +157,14 @@ pub enum Violation {
         /// The existing region.
         existing: Region,
     },
+    /// A freed region `requested` starts at the same location as an `existing`
+    /// region, but is larger than it.
+    OversizedFree {
+        /// The freed region.
+        requested: Region,
+        /// The existing region.
+        existing: Region,
+    },
     /// A freed region `requested` provided the wrong alignment metadata.
     /// See [std::alloc::Layout::align].
     MisalignedFree {
@@ -56,11 +178,63 @@ pub enum Violation {
         /// The freed region.
         requested: Region,
     },
+    /// An allocation of `requested` bytes was refused since it would exceed
+    /// the memory budget `max_bytes` of the allocator, with `memory_used`
+    /// bytes already live.
+    BudgetExceeded {
+        /// The number of bytes requested.
+        requested: usize,
+        /// The number of bytes live at the time of the request.
+        memory_used: usize,
+        /// The configured memory budget.
+        max_bytes: usize,
+    },
+    /// An allocation `requested` reused memory which was freed by the event
+    /// at index `freed_at`, within the window configured through
+    /// [Machine::with_tombstones].
+    ///
+    /// [Machine::with_tombstones]: crate::Machine::with_tombstones
+    QuickReuse {
+        /// The allocated region.
+        requested: Region,
+        /// The index of the event which freed the reused memory.
+        freed_at: usize,
+    },
+    /// A freed region `requested` was not the most recently allocated live
+    /// region `expected`, while frees are required to be in LIFO order
+    /// through [Machine::with_free_order].
+    ///
+    /// [Machine::with_free_order]: crate::Machine::with_free_order
+    OutOfOrderFree {
+        /// The freed region.
+        requested: Region,
+        /// The most recently allocated live region.
+        expected: Region,
+    },
     /// A `region` was leaked. In that it was allocated but never freed.
     Leaked {
         /// The leaked region.
         region: Region,
     },
+    /// An allocated region `requested` crosses a boundary between two pages,
+    /// even though it's no larger than a single page. See
+    /// [Machine::with_page_size].
+    ///
+    /// [Machine::with_page_size]: crate::Machine::with_page_size
+    CrossesPageBoundary {
+        /// The allocated region.
+        requested: Region,
+        /// The size of a page.
+        page_size: usize,
+    },
+    /// The region `free` was reallocated to a size of zero, while that's
+    /// reported through [Machine::with_strict_zero_reallocs].
+    ///
+    /// [Machine::with_strict_zero_reallocs]: crate::Machine::with_strict_zero_reallocs
+    ReallocToZero {
+        /// The region being reallocated.
+        free: Region,
+    },
 }
 
 /// A single violation to the virtual memory model of checkers.
@@ -90,6 +264,276 @@ impl Violation {
             _ => false,
         }
     }
+
+    /// Get the primary region involved in this violation.
+    ///
+    /// This is the `region` of [Leaked][Violation::Leaked], the `alloc` of
+    /// [NonCopiedRealloc][Violation::NonCopiedRealloc], the `free` of
+    /// [ReallocToZero][Violation::ReallocToZero] and the `requested` region
+    /// for every other violation. [ReallocNull][Violation::ReallocNull]
+    /// and [BudgetExceeded][Violation::BudgetExceeded] do not involve a region,
+    /// so they return `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Region, Violation};
+    ///
+    /// let region = Region::new(42.into(), 20, 4);
+    ///
+    /// assert_eq!(Some(region), Violation::Leaked { region }.requested());
+    /// assert_eq!(Some(region), Violation::MissingFree { requested: region }.requested());
+    /// assert_eq!(None, Violation::ReallocNull {}.requested());
+    /// ```
+    pub fn requested(&self) -> Option<Region> {
+        match *self {
+            Self::ConflictingAlloc { requested, .. }
+            | Self::NonZeroedAlloc { requested }
+            | Self::MisalignedAlloc { requested }
+            | Self::InvalidAlign { requested }
+            | Self::ExcessiveAlign { requested, .. }
+            | Self::RegionOverflow { requested }
+            | Self::ZeroSizedAlloc { requested }
+            | Self::IncompleteFree { requested, .. }
+            | Self::OversizedFree { requested, .. }
+            | Self::MisalignedFree { requested, .. }
+            | Self::MissingFree { requested }
+            | Self::QuickReuse { requested, .. }
+            | Self::OutOfOrderFree { requested, .. }
+            | Self::CrossesPageBoundary { requested, .. } => Some(requested),
+            Self::NonCopiedRealloc { alloc, .. } => Some(alloc),
+            Self::ReallocToZero { free } => Some(free),
+            Self::Leaked { region } => Some(region),
+            Self::ReallocNull {} | Self::BudgetExceeded { .. } => None,
+        }
+    }
+
+    /// Get the existing region involved in this violation, if any.
+    ///
+    /// This is the `existing` region of the violations which have one, the
+    /// `free` region of [NonCopiedRealloc][Violation::NonCopiedRealloc] and the
+    /// `expected` region of [OutOfOrderFree][Violation::OutOfOrderFree].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Region, Violation};
+    ///
+    /// let requested = Region::new(40.into(), 20, 4);
+    /// let existing = Region::new(42.into(), 20, 4);
+    ///
+    /// let violation = Violation::ConflictingAlloc { requested, existing };
+    /// assert_eq!(Some(existing), violation.existing());
+    ///
+    /// let violation = Violation::MissingFree { requested };
+    /// assert_eq!(None, violation.existing());
+    /// ```
+    pub fn existing(&self) -> Option<Region> {
+        match *self {
+            Self::ConflictingAlloc { existing, .. }
+            | Self::IncompleteFree { existing, .. }
+            | Self::OversizedFree { existing, .. }
+            | Self::MisalignedFree { existing, .. } => Some(existing),
+            Self::NonCopiedRealloc { free, .. } => Some(free),
+            Self::OutOfOrderFree { expected, .. } => Some(expected),
+            _ => None,
+        }
+    }
+
+    /// Get the name of the variant of this violation, like `"Leaked"`.
+    ///
+    /// Names are the same as the name of each variant, and will not change
+    /// between versions.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Region, Violation};
+    ///
+    /// let region = Region::new(42.into(), 20, 4);
+    /// assert_eq!("Leaked", Violation::Leaked { region }.name());
+    /// assert_eq!("ReallocNull", Violation::ReallocNull {}.name());
+    /// ```
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ConflictingAlloc { .. } => "ConflictingAlloc",
+            Self::NonZeroedAlloc { .. } => "NonZeroedAlloc",
+            Self::NonCopiedRealloc { .. } => "NonCopiedRealloc",
+            Self::ReallocNull {} => "ReallocNull",
+            Self::MisalignedAlloc { .. } => "MisalignedAlloc",
+            Self::InvalidAlign { .. } => "InvalidAlign",
+            Self::ExcessiveAlign { .. } => "ExcessiveAlign",
+            Self::RegionOverflow { .. } => "RegionOverflow",
+            Self::ZeroSizedAlloc { .. } => "ZeroSizedAlloc",
+            Self::IncompleteFree { .. } => "IncompleteFree",
+            Self::OversizedFree { .. } => "OversizedFree",
+            Self::MisalignedFree { .. } => "MisalignedFree",
+            Self::MissingFree { .. } => "MissingFree",
+            Self::BudgetExceeded { .. } => "BudgetExceeded",
+            Self::QuickReuse { .. } => "QuickReuse",
+            Self::OutOfOrderFree { .. } => "OutOfOrderFree",
+            Self::Leaked { .. } => "Leaked",
+            Self::CrossesPageBoundary { .. } => "CrossesPageBoundary",
+            Self::ReallocToZero { .. } => "ReallocToZero",
+        }
+    }
+
+    /// Get the stable numeric code of the variant of this violation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Region, Violation, ViolationCode};
+    ///
+    /// let region = Region::new(42.into(), 20, 4);
+    ///
+    /// let violation = Violation::MissingFree { requested: region };
+    /// assert_eq!(ViolationCode::MissingFree, violation.code());
+    /// assert_eq!(13, violation.code().value());
+    /// assert_eq!(17, Violation::Leaked { region }.code().value());
+    /// ```
+    pub fn code(&self) -> ViolationCode {
+        match self {
+            Self::ConflictingAlloc { .. } => ViolationCode::ConflictingAlloc,
+            Self::NonZeroedAlloc { .. } => ViolationCode::NonZeroedAlloc,
+            Self::NonCopiedRealloc { .. } => ViolationCode::NonCopiedRealloc,
+            Self::ReallocNull {} => ViolationCode::ReallocNull,
+            Self::MisalignedAlloc { .. } => ViolationCode::MisalignedAlloc,
+            Self::InvalidAlign { .. } => ViolationCode::InvalidAlign,
+            Self::ExcessiveAlign { .. } => ViolationCode::ExcessiveAlign,
+            Self::RegionOverflow { .. } => ViolationCode::RegionOverflow,
+            Self::ZeroSizedAlloc { .. } => ViolationCode::ZeroSizedAlloc,
+            Self::IncompleteFree { .. } => ViolationCode::IncompleteFree,
+            Self::OversizedFree { .. } => ViolationCode::OversizedFree,
+            Self::MisalignedFree { .. } => ViolationCode::MisalignedFree,
+            Self::MissingFree { .. } => ViolationCode::MissingFree,
+            Self::BudgetExceeded { .. } => ViolationCode::BudgetExceeded,
+            Self::QuickReuse { .. } => ViolationCode::QuickReuse,
+            Self::OutOfOrderFree { .. } => ViolationCode::OutOfOrderFree,
+            Self::Leaked { .. } => ViolationCode::Leaked,
+            Self::CrossesPageBoundary { .. } => ViolationCode::CrossesPageBoundary,
+            Self::ReallocToZero { .. } => ViolationCode::ReallocToZero,
+        }
+    }
+
+    /// Count the given violations by the [name][Violation::name] of their
+    /// variant.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Region, Severity, Violation};
+    ///
+    /// let region = Region::new(42.into(), 20, 4);
+    ///
+    /// let violations = vec![
+    ///     Violation::Leaked { region },
+    ///     Violation::Leaked { region },
+    ///     Violation::MissingFree { requested: region },
+    /// ];
+    ///
+    /// let summary = Violation::summary(&violations);
+    /// assert_eq!(Some(&2), summary.get("Leaked"));
+    /// assert_eq!(Some(&1), summary.get("MissingFree"));
+    /// assert_eq!(None, summary.get("ConflictingAlloc"));
+    ///
+    /// let warnings = violations.iter().filter(|v| v.severity() == Severity::Warning);
+    /// assert_eq!(2, warnings.count());
+    /// ```
+    pub fn summary<'a, I>(violations: I) -> BTreeMap<&'static str, usize>
+    where
+        I: IntoIterator<Item = &'a Violation>,
+    {
+        let mut summary = BTreeMap::new();
+
+        for violation in violations {
+            *summary.entry(violation.name()).or_default() += 1;
+        }
+
+        summary
+    }
+
+    /// Classify the severity of this violation.
+    ///
+    /// * [Severity::Warning] for [Leaked][Violation::Leaked] and
+    ///   [QuickReuse][Violation::QuickReuse].
+    /// * [Severity::Error] for [NonZeroedAlloc][Violation::NonZeroedAlloc],
+    ///   [ReallocNull][Violation::ReallocNull],
+    ///   [MisalignedAlloc][Violation::MisalignedAlloc],
+    ///   [InvalidAlign][Violation::InvalidAlign],
+    ///   [ExcessiveAlign][Violation::ExcessiveAlign],
+    ///   [RegionOverflow][Violation::RegionOverflow],
+    ///   [ZeroSizedAlloc][Violation::ZeroSizedAlloc],
+    ///   [BudgetExceeded][Violation::BudgetExceeded],
+    ///   [OutOfOrderFree][Violation::OutOfOrderFree],
+    ///   [CrossesPageBoundary][Violation::CrossesPageBoundary] and
+    ///   [ReallocToZero][Violation::ReallocToZero].
+    /// * [Severity::Critical] for [ConflictingAlloc][Violation::ConflictingAlloc],
+    ///   [NonCopiedRealloc][Violation::NonCopiedRealloc],
+    ///   [IncompleteFree][Violation::IncompleteFree],
+    ///   [OversizedFree][Violation::OversizedFree],
+    ///   [MisalignedFree][Violation::MisalignedFree] and
+    ///   [MissingFree][Violation::MissingFree].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Region, Severity, Violation};
+    ///
+    /// let region = Region::new(42.into(), 20, 4);
+    ///
+    /// let violations = vec![
+    ///     Violation::Leaked { region },
+    ///     Violation::MissingFree { requested: region },
+    /// ];
+    ///
+    /// assert_eq!(Severity::Warning, violations[0].severity());
+    /// assert_eq!(Severity::Critical, violations[1].severity());
+    /// assert!(violations.iter().any(|v| v.severity() >= Severity::Error));
+    /// ```
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::Leaked { .. } | Self::QuickReuse { .. } => Severity::Warning,
+            Self::NonZeroedAlloc { .. }
+            | Self::ReallocNull {}
+            | Self::MisalignedAlloc { .. }
+            | Self::InvalidAlign { .. }
+            | Self::ExcessiveAlign { .. }
+            | Self::RegionOverflow { .. }
+            | Self::ZeroSizedAlloc { .. }
+            | Self::BudgetExceeded { .. }
+            | Self::OutOfOrderFree { .. }
+            | Self::CrossesPageBoundary { .. }
+            | Self::ReallocToZero { .. } => Severity::Error,
+            Self::ConflictingAlloc { .. }
+            | Self::NonCopiedRealloc { .. }
+            | Self::IncompleteFree { .. }
+            | Self::OversizedFree { .. }
+            | Self::MisalignedFree { .. }
+            | Self::MissingFree { .. } => Severity::Critical,
+        }
+    }
+
+    /// Test if this violation indicates that memory was or is likely to be
+    /// corrupted, like a double-free or two allocations sharing memory.
+    ///
+    /// This is the case for exactly the violations classified as
+    /// [Severity::Critical] by [severity][Violation::severity].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use checkers::{Region, Violation};
+    ///
+    /// let region = Region::new(42.into(), 20, 4);
+    ///
+    /// assert!(Violation::MissingFree { requested: region }.is_corruption());
+    /// assert!(!Violation::Leaked { region }.is_corruption());
+    /// assert!(!Violation::ZeroSizedAlloc { requested: region }.is_corruption());
+    /// ```
+    pub fn is_corruption(&self) -> bool {
+        self.severity() == Severity::Critical
+    }
 }
 
 impl fmt::Display for Violation {
@@ -98,11 +542,20 @@ impl fmt::Display for Violation {
             Self::ConflictingAlloc {
                 requested,
                 existing,
-            } => write!(
-                fmt,
-                "Requested allocation ({}) overlaps with existing ({})",
-                requested, existing
-            ),
+            } => match requested.intersection(*existing) {
+                Some(overlap) => write!(
+                    fmt,
+                    "Requested allocation ({}) overlaps with existing ({}) by {}",
+                    requested,
+                    existing,
+                    Bytes(overlap.size)
+                ),
+                None => write!(
+                    fmt,
+                    "Requested allocation ({}) overlaps with existing ({})",
+                    requested, existing
+                ),
+            },
             Self::NonZeroedAlloc { requested } => write!(
                 fmt,
                 "Requested allocation ({}) was not zerod by the allocator",
@@ -114,8 +567,29 @@ impl fmt::Display for Violation {
                 free, alloc,
             ),
             Self::ReallocNull {} => write!(fmt, "Tried to reallocate null pointer"),
-            Self::MisalignedAlloc { requested } => {
-                write!(fmt, "Allocated region ({}) is misaligned.", requested)
+            Self::MisalignedAlloc { requested } => write!(
+                fmt,
+                "Allocated region ({}) is misaligned, off by {}.",
+                requested,
+                Bytes(requested.ptr.align_offset(requested.align))
+            ),
+            Self::InvalidAlign { requested } => write!(
+                fmt,
+                "Region ({}) has an alignment which is not a power of two",
+                requested
+            ),
+            Self::ExcessiveAlign { requested, max } => write!(
+                fmt,
+                "Region ({}) has an alignment larger than the maximum of {}",
+                requested, max
+            ),
+            Self::RegionOverflow { requested } => write!(
+                fmt,
+                "Region ({}) extends past the end of the address space",
+                requested
+            ),
+            Self::ZeroSizedAlloc { requested } => {
+                write!(fmt, "Allocated region ({}) is zero-sized", requested)
             }
             Self::IncompleteFree {
                 requested,
@@ -125,6 +599,14 @@ impl fmt::Display for Violation {
                 "Freed ({}) only part of existing region ({})",
                 requested, existing
             ),
+            Self::OversizedFree {
+                requested,
+                existing,
+            } => write!(
+                fmt,
+                "Freed ({}) more than existing region ({})",
+                requested, existing
+            ),
             Self::MisalignedFree {
                 requested,
                 existing,
@@ -134,7 +616,49 @@ impl fmt::Display for Violation {
                 requested, existing
             ),
             Self::MissingFree { requested } => write!(fmt, "Freed missing region ({})", requested),
+            Self::BudgetExceeded {
+                requested,
+                memory_used,
+                max_bytes,
+            } => write!(
+                fmt,
+                "Allocating {} with {} in use exceeds the budget of {}",
+                Bytes(*requested),
+                Bytes(*memory_used),
+                Bytes(*max_bytes)
+            ),
+            Self::QuickReuse {
+                requested,
+                freed_at,
+            } => write!(
+                fmt,
+                "Allocated region ({}) reuses memory freed by event {}",
+                requested, freed_at
+            ),
+            Self::OutOfOrderFree {
+                requested,
+                expected,
+            } => write!(
+                fmt,
+                "Freed region ({}) is not the most recently allocated region ({})",
+                requested, expected
+            ),
             Self::Leaked { region } => write!(fmt, "Dangling region ({})", region),
+            Self::CrossesPageBoundary {
+                requested,
+                page_size,
+            } => write!(
+                fmt,
+                "Allocated region ({}) crosses a boundary between pages of {}",
+                requested,
+                Bytes(*page_size)
+            ),
+            Self::ReallocToZero { free } => {
+                write!(fmt, "Region ({}) was reallocated to a size of zero", free)
+            }
         }
     }
 }
+
+#[cfg(feature = "std")]
+impl error::Error for Violation {}
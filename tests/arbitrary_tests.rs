@@ -0,0 +1,32 @@
+#![cfg(feature = "arbitrary")]
+
+use arbitrary::{Arbitrary, Unstructured};
+use checkers::{Event, Machine, Region};
+
+#[test]
+fn arbitrary_regions_are_aligned() {
+    let data = (0..=255u8).cycle().take(4096).collect::<Vec<_>>();
+    let mut u = Unstructured::new(&data);
+
+    while let Ok(region) = Region::arbitrary(&mut u) {
+        if u.is_empty() {
+            break;
+        }
+
+        assert!(region.align.is_power_of_two());
+        assert!(region.align <= 1 << 15);
+    }
+}
+
+#[test]
+fn arbitrary_events_do_not_panic() {
+    let data = (0..=255u8).rev().cycle().take(4096).collect::<Vec<_>>();
+    let mut u = Unstructured::new(&data);
+    let mut machine = Machine::default();
+
+    while !u.is_empty() {
+        if let Ok(event) = Event::arbitrary(&mut u) {
+            let _ = machine.push(event);
+        }
+    }
+}
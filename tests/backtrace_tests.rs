@@ -0,0 +1,24 @@
+#![cfg(feature = "backtrace")]
+
+#[global_allocator]
+static ALLOCATOR: checkers::Allocator = checkers::Allocator::system().with_backtraces(4);
+
+#[test]
+fn test_backtrace_depth() {
+    let snapshot = checkers::with(|| {
+        let b = Box::into_raw(Box::new(42u32));
+        let _ = unsafe { Box::from_raw(b) };
+    });
+
+    assert_eq!(2, snapshot.events.len());
+    assert_eq!(1, snapshot.backtraces.len());
+
+    let region = match snapshot.events[0] {
+        checkers::Event::Alloc(region) => region,
+        _ => panic!("expected allocation"),
+    };
+
+    let backtrace = snapshot.alloc_backtrace(region).unwrap();
+    assert!(!backtrace.frames().is_empty());
+    assert!(backtrace.frames().len() <= 4);
+}
@@ -0,0 +1,99 @@
+use checkers::{
+    AllocZeroed, BudgetExceeded, DecodeError, Event, Events, Realloc, Region, Snapshot,
+};
+
+fn snapshot(events: &[Event]) -> Snapshot {
+    let mut collected = Events::new();
+
+    for &event in events {
+        collected.push(event);
+    }
+
+    Snapshot {
+        events: collected,
+        #[cfg(feature = "backtrace")]
+        backtraces: Default::default(),
+        peak_memory: 128,
+        max_bytes: Some(1024),
+        skipped_allocs: 3,
+    }
+}
+
+#[test]
+fn test_binary_roundtrip() {
+    let a = Region::new(0x1000.into(), 64, 8);
+    let b = Region::new(0x2000.into(), 128, 16);
+
+    let events = [
+        Event::Alloc(a),
+        Event::Free(a),
+        Event::AllocZeroed(AllocZeroed::new(Some(false), a)),
+        Event::Realloc(Realloc::new(Some(true), a, b)),
+        Event::Realloc(Realloc::new(None, b, a)),
+        Event::AllocFailed,
+        Event::AllocZeroedFailed,
+        Event::ReallocNull,
+        Event::ReallocFailed,
+        Event::BudgetExceeded(BudgetExceeded::new(100, 200, 300)),
+        Event::InjectedFailure(Region::new(0.into(), 32, 4)),
+    ];
+
+    let original = snapshot(&events);
+    let bytes = original.to_bytes();
+    assert_eq!(4 + 1 + 8 + 1 + 8 + 8 + 8 + events.len() * 50, bytes.len());
+
+    let decoded = Snapshot::from_bytes(&bytes).unwrap();
+    assert_eq!(&events[..], decoded.events.as_slice());
+    assert_eq!(128, decoded.peak_memory);
+    assert_eq!(Some(1024), decoded.max_bytes);
+    assert_eq!(3, decoded.skipped_allocs);
+
+    let mut unlimited = snapshot(&[]);
+    unlimited.max_bytes = None;
+    let decoded = Snapshot::from_bytes(&unlimited.to_bytes()).unwrap();
+    assert_eq!(None, decoded.max_bytes);
+    assert!(decoded.events.is_empty());
+}
+
+#[test]
+fn test_binary_errors() {
+    let bytes = snapshot(&[Event::Alloc(Region::new(0x1000.into(), 64, 8))]).to_bytes();
+
+    assert_eq!(
+        Some(DecodeError::UnexpectedEnd),
+        Snapshot::from_bytes(&bytes[..bytes.len() - 1]).err()
+    );
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_eq!(
+        Some(DecodeError::TrailingBytes),
+        Snapshot::from_bytes(&trailing).err()
+    );
+
+    let mut version = bytes.clone();
+    version[4] = 42;
+    assert_eq!(
+        Some(DecodeError::UnsupportedVersion { version: 42 }),
+        Snapshot::from_bytes(&version).err()
+    );
+
+    let header = bytes.len() - 50;
+
+    let mut kind = bytes.clone();
+    kind[header] = 200;
+    assert_eq!(
+        Some(DecodeError::InvalidKind {
+            index: 0,
+            kind: 200
+        }),
+        Snapshot::from_bytes(&kind).err()
+    );
+
+    let mut flag = bytes;
+    flag[header + 1] = 3;
+    assert_eq!(
+        Some(DecodeError::InvalidFlag { index: 0, flag: 3 }),
+        Snapshot::from_bytes(&flag).err()
+    );
+}
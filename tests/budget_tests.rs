@@ -0,0 +1,26 @@
+use checkers::{BudgetExceeded, Event};
+
+#[global_allocator]
+static ALLOCATOR: checkers::Allocator = checkers::Allocator::system().with_max_bytes(64);
+
+#[test]
+fn test_budget_realloc() {
+    let snapshot = checkers::with(|| {
+        let mut v = Vec::<u8>::new();
+        assert!(v.try_reserve_exact(32).is_ok());
+        assert!(v.try_reserve_exact(64).is_ok());
+        assert!(v.try_reserve_exact(65).is_err());
+    });
+
+    assert_eq!(4, snapshot.events.len());
+    assert!(snapshot.events[0].is_alloc_with(|r| r.size == 32));
+    assert!(snapshot.events[1].is_realloc_with(|r| r.alloc.size == 64));
+    assert_eq!(
+        Event::BudgetExceeded(BudgetExceeded::new(65, 64, 64)),
+        snapshot.events[2]
+    );
+    assert!(snapshot.events[3].is_free_with(|r| r.size == 64));
+
+    assert_eq!(Some(64), snapshot.max_bytes);
+    assert_eq!(64, snapshot.peak_memory);
+}
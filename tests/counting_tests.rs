@@ -0,0 +1,34 @@
+use checkers::CountingAllocator;
+use std::alloc::{GlobalAlloc, Layout};
+
+// Note: a dedicated instance, since the global allocator also counts
+// allocations performed by the test harness.
+static COUNTER: CountingAllocator = CountingAllocator::system();
+
+#[test]
+fn test_counting() {
+    let small = Layout::from_size_align(16, 8).unwrap();
+    let large = Layout::from_size_align(64, 8).unwrap();
+
+    unsafe {
+        let a = COUNTER.alloc(small);
+        let b = COUNTER.alloc_zeroed(large);
+        assert_eq!(2, COUNTER.allocs());
+        assert_eq!(80, COUNTER.memory_used());
+
+        let a = COUNTER.realloc(a, small, 32);
+        assert_eq!(96, COUNTER.memory_used());
+        assert_eq!(96, COUNTER.peak_memory());
+
+        COUNTER.dealloc(b, large);
+        let a = COUNTER.realloc(a, Layout::from_size_align(32, 8).unwrap(), 8);
+        assert_eq!(8, COUNTER.memory_used());
+
+        COUNTER.dealloc(a, Layout::from_size_align(8, 8).unwrap());
+    }
+
+    assert_eq!(2, COUNTER.allocs());
+    assert_eq!(2, COUNTER.frees());
+    assert_eq!(0, COUNTER.memory_used());
+    assert_eq!(96, COUNTER.peak_memory());
+}
@@ -0,0 +1,47 @@
+#[global_allocator]
+static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+
+#[test]
+fn test_csv_columns() {
+    let snapshot = checkers::with(|| {
+        let mut a = Vec::<u8>::with_capacity(16);
+        a.reserve_exact(32);
+        let b = Box::new(0u32);
+        drop(a);
+        drop(b);
+    });
+
+    let mut out = Vec::new();
+    snapshot.to_csv(&mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    let rows = out
+        .lines()
+        .map(|line| line.split(',').collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    assert_eq!(6, rows.len());
+    assert_eq!(
+        vec!["index", "kind", "ptr", "size", "align", "memory_used"],
+        rows[0]
+    );
+
+    let columns = |row: &[&str]| (row[1].to_owned(), row[3].to_owned(), row[5].to_owned());
+
+    assert_eq!(
+        ("alloc".into(), "16".into(), "16".into()),
+        columns(&rows[1])
+    );
+    assert_eq!(
+        ("realloc".into(), "32".into(), "32".into()),
+        columns(&rows[2])
+    );
+    assert_eq!(("alloc".into(), "4".into(), "36".into()), columns(&rows[3]));
+    assert_eq!(("free".into(), "32".into(), "4".into()), columns(&rows[4]));
+    assert_eq!(("free".into(), "4".into(), "0".into()), columns(&rows[5]));
+
+    for (index, row) in rows[1..].iter().enumerate() {
+        assert_eq!(index.to_string(), row[0]);
+        assert!(row[2].starts_with("0x"));
+    }
+}
@@ -10,3 +10,29 @@ fn events_test() {
 
     assert_eq!(800, events.as_slice().len());
 }
+
+#[test]
+fn foreign_frees_test() {
+    let a = Region::new(10.into(), 10, 1);
+    let b = Region::new(20.into(), 10, 1);
+
+    let mut events = Events::new();
+    events.push(Event::Alloc(a));
+    events.push(Event::Alloc(b));
+    events.push(Event::Free(a));
+
+    let allocated = std::thread::current().id();
+
+    let (mut events, freed) = std::thread::spawn(move || {
+        events.push(Event::Free(b));
+        (events, std::thread::current().id())
+    })
+    .join()
+    .unwrap();
+
+    events.push(Event::Alloc(a));
+
+    assert_eq!(vec![(b, allocated, freed)], events.foreign_frees());
+    assert_eq!(1, events.by_thread(freed).count());
+    assert_eq!(4, events.by_thread(allocated).count());
+}
@@ -0,0 +1,34 @@
+#![cfg(feature = "json")]
+
+#[global_allocator]
+static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+
+#[test]
+fn test_json_schema() {
+    let snapshot = checkers::with(|| {
+        let _ = vec![1u32, 2, 3, 4];
+    });
+
+    let mut out = Vec::new();
+    snapshot.to_writer(&mut out).unwrap();
+    assert_eq!(snapshot.to_json().as_bytes(), &out[..]);
+
+    let value = serde_json::from_slice::<serde_json::Value>(&out).unwrap();
+    assert_eq!(1, value["allocs"]);
+    assert_eq!(0, value["reallocs"]);
+    assert_eq!(1, value["frees"]);
+
+    let events = value["events"].as_array().unwrap();
+    assert_eq!(2, events.len());
+
+    assert_eq!(0, events[0]["index"]);
+    assert_eq!("alloc", events[0]["kind"]);
+    assert_eq!(16, events[0]["region"]["size"]);
+    assert_eq!(4, events[0]["region"]["align"]);
+    assert!(events[0]["region"]["ptr"].is_u64());
+    assert!(events[0]["free"].is_null());
+
+    assert_eq!(1, events[1]["index"]);
+    assert_eq!("free", events[1]["kind"]);
+    assert_eq!(events[0]["region"], events[1]["region"]);
+}
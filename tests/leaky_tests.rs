@@ -13,6 +13,19 @@ fn test_non_leak_box() {
     let _ = unsafe { Box::from_raw(b) };
 }
 
+#[checkers::test]
+#[should_panic(expected = "Dangling region")]
+fn test_leak_report() {
+    let _ = Box::into_raw(Box::new(0u128));
+}
+
+#[checkers::test]
+fn test_return_result() -> Result<(), std::num::ParseIntError> {
+    let n = String::from("42").parse::<u32>()?;
+    assert_eq!(42, n);
+    Ok(())
+}
+
 fn verify_test_custom_verify(state: &mut checkers::State) {
     let mut violations = Vec::new();
     state.validate(&mut violations);
@@ -0,0 +1,955 @@
+use checkers::{AllocZeroed, Event::*, FreeOrder, Machine, Realloc, Region, Violation};
+
+#[test]
+fn test_realloc_in_place() {
+    let mut machine = Machine::default();
+    let free = Region::new(100.into(), 10, 1);
+    let alloc = Region::new(100.into(), 20, 1);
+
+    assert!(machine.push(Alloc(free)).is_ok());
+    assert!(machine
+        .push(Realloc(Realloc::new(Some(true), free, alloc)))
+        .is_ok());
+
+    assert_eq!(20, machine.memory_used());
+    assert_eq!(vec![alloc], machine.trailing_regions());
+}
+
+#[test]
+fn test_realloc_moved() {
+    let mut machine = Machine::default();
+    let free = Region::new(100.into(), 10, 1);
+    let alloc = Region::new(200.into(), 4, 1);
+
+    assert!(machine.push(Alloc(free)).is_ok());
+    assert!(machine
+        .push(Realloc(Realloc::new(Some(true), free, alloc)))
+        .is_ok());
+
+    assert_eq!(4, machine.memory_used());
+    assert_eq!(vec![alloc], machine.trailing_regions());
+}
+
+#[test]
+fn test_realloc_conflicting_growth() {
+    let mut machine = Machine::default();
+    let free = Region::new(100.into(), 10, 1);
+    let existing = Region::new(110.into(), 10, 1);
+    let requested = Region::new(100.into(), 20, 1);
+
+    assert!(machine.push(Alloc(free)).is_ok());
+    assert!(machine.push(Alloc(existing)).is_ok());
+
+    assert_eq!(
+        Err(Violation::ConflictingAlloc {
+            requested,
+            existing
+        }),
+        machine.push(Realloc(Realloc::new(Some(true), free, requested)))
+    );
+
+    // Note: machine is left unaltered.
+    assert_eq!(20, machine.memory_used());
+    assert_eq!(vec![free, existing], machine.trailing_regions());
+}
+
+#[test]
+fn test_realloc_missing() {
+    let mut machine = Machine::default();
+    let free = Region::new(100.into(), 10, 1);
+    let alloc = Region::new(200.into(), 20, 1);
+
+    assert_eq!(
+        Err(Violation::MissingFree { requested: free }),
+        machine.push(Realloc(Realloc::new(Some(true), free, alloc)))
+    );
+
+    assert_eq!(0, machine.memory_used());
+    assert!(machine.trailing_regions().is_empty());
+}
+
+#[test]
+fn test_region_overlaps() {
+    let a = Region::new(100.into(), 100, 1);
+
+    // Containment.
+    let b = Region::new(120.into(), 10, 1);
+    assert!(a.overlaps(b));
+    assert!(b.overlaps(a));
+
+    // Partial overlap at the start of `a`.
+    let b = Region::new(50.into(), 60, 1);
+    assert!(a.overlaps(b));
+    assert!(b.overlaps(a));
+
+    // Partial overlap at the end of `a`.
+    let b = Region::new(150.into(), 100, 1);
+    assert!(a.overlaps(b));
+    assert!(b.overlaps(a));
+
+    // Adjacent regions do not overlap.
+    let b = Region::new(200.into(), 10, 1);
+    assert!(!a.overlaps(b));
+    assert!(!b.overlaps(a));
+
+    let b = Region::new(90.into(), 10, 1);
+    assert!(!a.overlaps(b));
+    assert!(!b.overlaps(a));
+}
+
+#[test]
+fn test_conflicting_alloc_straddling() {
+    let mut machine = Machine::default();
+    let existing = Region::new(110.into(), 10, 1);
+    let requested = Region::new(100.into(), 100, 1);
+
+    assert!(machine.push(Alloc(existing)).is_ok());
+    assert_eq!(
+        Err(Violation::ConflictingAlloc {
+            requested,
+            existing
+        }),
+        machine.push(Alloc(requested))
+    );
+}
+
+#[test]
+fn test_double_alloc() {
+    let mut machine = Machine::default();
+    let existing = Region::new(100.into(), 10, 1);
+
+    assert!(machine.push(Alloc(existing)).is_ok());
+    assert_eq!(
+        Err(Violation::ConflictingAlloc {
+            requested: existing,
+            existing
+        }),
+        machine.push(Alloc(existing))
+    );
+
+    assert_eq!(10, machine.memory_used());
+    assert_eq!(vec![existing], machine.trailing_regions());
+}
+
+#[test]
+fn test_invalid_align() {
+    let mut machine = Machine::default();
+    let requested = Region::new(0.into(), 10, 0);
+
+    assert_eq!(
+        Err(Violation::InvalidAlign { requested }),
+        machine.push(Alloc(requested))
+    );
+    assert_eq!(
+        Err(Violation::InvalidAlign { requested }),
+        machine.push(Free(requested))
+    );
+}
+
+#[test]
+fn test_partial_frees() {
+    let mut machine = Machine::default().with_partial_frees(true);
+    let existing = Region::new(100.into(), 100, 1);
+
+    assert!(machine.push(Alloc(existing)).is_ok());
+
+    // Prefix.
+    assert!(machine.push(Free(Region::new(100.into(), 50, 1))).is_ok());
+    assert_eq!(50, machine.memory_used());
+    assert_eq!(
+        vec![Region::new(150.into(), 50, 1)],
+        machine.trailing_regions()
+    );
+
+    // Middle.
+    assert!(machine.push(Free(Region::new(160.into(), 10, 1))).is_ok());
+    assert_eq!(40, machine.memory_used());
+    assert_eq!(
+        vec![
+            Region::new(150.into(), 10, 1),
+            Region::new(170.into(), 30, 1)
+        ],
+        machine.trailing_regions()
+    );
+
+    // Past the end of the region.
+    let requested = Region::new(180.into(), 30, 1);
+    assert_eq!(
+        Err(Violation::IncompleteFree {
+            requested,
+            existing: Region::new(170.into(), 30, 1),
+        }),
+        machine.push(Free(requested))
+    );
+
+    // Suffix.
+    assert!(machine.push(Free(Region::new(180.into(), 20, 1))).is_ok());
+    assert_eq!(20, machine.memory_used());
+
+    // Not allocated.
+    let requested = Region::new(160.into(), 10, 1);
+    assert_eq!(
+        Err(Violation::MissingFree { requested }),
+        machine.push(Free(requested))
+    );
+}
+
+#[test]
+fn test_violation_as_error() -> Result<(), Box<dyn std::error::Error>> {
+    let mut machine = Machine::default();
+    let region = Region::new(100.into(), 10, 1);
+
+    machine.push(Alloc(region))?;
+    machine.push(Free(region))?;
+
+    let error: Box<dyn std::error::Error> = machine.push(Free(region)).unwrap_err().into();
+    assert!(error.source().is_none());
+    Ok(())
+}
+
+#[test]
+fn test_region_intersection() {
+    let a = Region::new(100.into(), 100, 1);
+
+    // Containment.
+    let b = Region::new(120.into(), 10, 4);
+    assert_eq!(Some(Region::new(120.into(), 10, 1)), a.intersection(b));
+    assert_eq!(Some(b), b.intersection(a));
+
+    // Touching regions.
+    let b = Region::new(200.into(), 10, 1);
+    assert_eq!(None, a.intersection(b));
+    assert_eq!(None, b.intersection(a));
+}
+
+#[test]
+fn test_region_coalesce() {
+    let a = Region::new(100.into(), 100, 1);
+
+    // Apart.
+    let b = Region::new(201.into(), 10, 1);
+    assert!(!a.is_adjacent_to(b));
+    assert_eq!(None, a.coalesce(b));
+    assert_eq!(None, b.coalesce(a));
+
+    // Overlapping.
+    let b = Region::new(150.into(), 100, 1);
+    assert!(!a.is_adjacent_to(b));
+    assert_eq!(None, a.coalesce(b));
+    assert_eq!(None, b.coalesce(a));
+
+    // Identical.
+    assert_eq!(None, a.coalesce(a));
+
+    // Adjacent, but with different alignments.
+    let b = Region::new(200.into(), 10, 2);
+    assert!(a.is_adjacent_to(b));
+    assert_eq!(None, a.coalesce(b));
+
+    let b = Region::new(200.into(), 10, 1);
+    assert_eq!(Some(Region::new(100.into(), 110, 1)), a.coalesce(b));
+}
+
+#[test]
+fn test_free_mismatch_precedence() {
+    let existing = Region::new(100.into(), 100, 4);
+
+    let check = |partial_frees, requested: Region| {
+        let mut machine = Machine::default().with_partial_frees(partial_frees);
+        assert!(machine.push(Alloc(existing)).is_ok());
+        let free = machine.push(Free(requested)).unwrap_err();
+
+        let mut machine = Machine::default();
+        assert!(machine.push(Alloc(existing)).is_ok());
+        let realloc = machine
+            .push(Realloc(Realloc::new(
+                None,
+                requested,
+                Region::new(300.into(), 10, 1),
+            )))
+            .unwrap_err();
+
+        assert_eq!(free, realloc);
+        free
+    };
+
+    for &partial_frees in &[false, true] {
+        // Both size and alignment differ.
+        let requested = Region::new(100.into(), 50, 8);
+        assert_eq!(
+            Violation::MisalignedFree {
+                requested,
+                existing
+            },
+            check(partial_frees, requested)
+        );
+
+        let requested = Region::new(100.into(), 150, 8);
+        assert_eq!(
+            Violation::MisalignedFree {
+                requested,
+                existing
+            },
+            check(partial_frees, requested)
+        );
+
+        // Only size differs.
+        let requested = Region::new(100.into(), 150, 4);
+        assert_eq!(
+            Violation::OversizedFree {
+                requested,
+                existing
+            },
+            check(partial_frees, requested)
+        );
+    }
+
+    let requested = Region::new(100.into(), 50, 4);
+    assert_eq!(
+        Violation::IncompleteFree {
+            requested,
+            existing
+        },
+        check(false, requested)
+    );
+}
+
+#[test]
+fn test_overlapping() {
+    let mut machine = Machine::default();
+    let a = Region::new(100.into(), 100, 1);
+    let b = Region::new(200.into(), 100, 1);
+    let c = Region::new(300.into(), 100, 1);
+
+    for &region in &[c, a, b] {
+        assert!(machine.push(Alloc(region)).is_ok());
+    }
+
+    let overlapping = |ptr: usize, size| {
+        machine
+            .overlapping(Region::new(ptr.into(), size, 1))
+            .collect::<Vec<_>>()
+    };
+
+    // Starting exactly at a region is only reported once.
+    assert_eq!(vec![b], overlapping(200, 10));
+    assert_eq!(vec![a, b, c], overlapping(100, 300));
+    assert_eq!(vec![a, b, c], overlapping(150, 200));
+    assert_eq!(vec![b, c], overlapping(200, 101));
+    assert_eq!(Vec::<Region>::new(), overlapping(400, 10));
+    assert_eq!(Vec::<Region>::new(), overlapping(0, 100));
+    assert_eq!(vec![a], overlapping(150, 0));
+}
+
+#[test]
+fn test_tombstones() {
+    let a = Region::new(100.into(), 100, 1);
+    let b = Region::new(300.into(), 100, 1);
+
+    // Reallocations which move a region leave a tombstone.
+    let mut machine = Machine::default().with_tombstones(1);
+    assert!(machine.push(Alloc(a)).is_ok());
+    assert!(machine.push(Realloc(Realloc::new(None, a, b))).is_ok());
+
+    let requested = Region::new(150.into(), 10, 1);
+    assert_eq!(
+        Err(Violation::QuickReuse {
+            requested,
+            freed_at: 1
+        }),
+        machine.push(Alloc(requested))
+    );
+    assert_eq!(110, machine.memory_used());
+
+    // An empty window never reports reuse.
+    let mut machine = Machine::default().with_tombstones(0);
+    assert!(machine.push(Alloc(a)).is_ok());
+    assert!(machine.push(Free(a)).is_ok());
+    assert!(machine.push(Alloc(a)).is_ok());
+
+    // Disabled by default.
+    let mut machine = Machine::default();
+    assert!(machine.push(Alloc(a)).is_ok());
+    assert!(machine.push(Free(a)).is_ok());
+    assert!(machine.push(Alloc(a)).is_ok());
+}
+
+#[test]
+fn test_snapshot_restore() {
+    let a = Region::new(100.into(), 100, 1);
+    let b = Region::new(300.into(), 300, 1);
+
+    let mut machine = Machine::default();
+    assert!(machine.push(Alloc(a)).is_ok());
+
+    let state = machine.snapshot_state();
+
+    for _ in 0..2 {
+        machine.restore(state.clone());
+        assert!(machine.push(Alloc(b)).is_ok());
+        assert!(machine.push(Free(a)).is_ok());
+        assert_eq!(400, machine.peak_memory());
+        assert_eq!(400, machine.total_allocated());
+    }
+
+    machine.restore(state);
+    assert_eq!(100, machine.memory_used());
+    assert_eq!(100, machine.peak_memory());
+    assert_eq!(100, machine.total_allocated());
+    assert_eq!(vec![a], machine.trailing_regions());
+}
+
+#[test]
+fn test_provenance() {
+    let a = Region::new(100.into(), 100, 1);
+    let b = Region::new(300.into(), 100, 1);
+
+    let mut machine = Machine::default()
+        .with_partial_frees(true)
+        .with_provenance(true);
+
+    assert!(machine.push(Alloc(a)).is_ok());
+    assert!(machine.push(Alloc(b)).is_ok());
+    assert!(machine.push(Free(Region::new(140.into(), 20, 1))).is_ok());
+
+    assert_eq!(Some(0), machine.established_at(100.into()));
+    assert_eq!(None, machine.established_at(150.into()));
+    assert_eq!(Some(0), machine.established_at(160.into()));
+    assert_eq!(Some(1), machine.established_at(399.into()));
+
+    // Disabled by default.
+    let mut machine = Machine::default();
+    assert!(machine.push(Alloc(a)).is_ok());
+    assert_eq!(None, machine.established_at(100.into()));
+}
+
+#[test]
+fn test_conflicting_alloc_message() {
+    let violation = Violation::ConflictingAlloc {
+        requested: Region::new(0x10.into(), 16, 1),
+        existing: Region::new(0x18.into(), 16, 1),
+    };
+
+    assert_eq!(
+        "Requested allocation (0x10-0x20 (size: 16 B, align: 1)) overlaps with existing (0x18-0x28 (size: 16 B, align: 1)) by 8 B",
+        violation.to_string()
+    );
+}
+
+#[test]
+fn test_misaligned_alloc_message() {
+    let violation = Violation::MisalignedAlloc {
+        requested: Region::new(0x1006.into(), 16, 8),
+    };
+
+    assert_eq!(
+        "Allocated region (0x1006-0x1016 (size: 16 B, align: 8)) is misaligned, off by 6 B.",
+        violation.to_string()
+    );
+}
+
+#[test]
+fn test_human_readable_sizes() {
+    let display = |size| Region::new(0.into(), size, 1).to_string();
+
+    assert_eq!("0x0-0x200 (size: 512 B, align: 1)", display(512));
+    assert_eq!("0x0-0x400 (size: 1.00 KiB, align: 1)", display(1024));
+    assert_eq!("0x0-0x4000 (size: 16.0 KiB, align: 1)", display(16 * 1024));
+    assert_eq!("0x0-0x80000 (size: 512 KiB, align: 1)", display(512 * 1024));
+    assert_eq!(
+        "0x0-0x180000 (size: 1.50 MiB, align: 1)",
+        display(1536 * 1024)
+    );
+
+    let violation = Violation::BudgetExceeded {
+        requested: 2048,
+        memory_used: 100,
+        max_bytes: 1024,
+    };
+
+    assert_eq!(
+        "Allocating 2.00 KiB with 100 B in use exceeds the budget of 1.00 KiB",
+        violation.to_string()
+    );
+}
+
+#[test]
+fn test_undersized_free() {
+    let mut machine = Machine::default();
+    let existing = Region::new(100.into(), 20, 1);
+    let requested = Region::new(100.into(), 10, 1);
+
+    assert!(machine.push(Alloc(existing)).is_ok());
+    assert_eq!(
+        Err(Violation::IncompleteFree {
+            requested,
+            existing
+        }),
+        machine.push(Free(requested))
+    );
+    assert_eq!(
+        Err(Violation::IncompleteFree {
+            requested,
+            existing
+        }),
+        machine.push(Realloc(Realloc::new(
+            None,
+            requested,
+            Region::new(200.into(), 10, 1)
+        )))
+    );
+}
+
+#[test]
+fn test_oversized_free() {
+    let mut machine = Machine::default();
+    let existing = Region::new(100.into(), 20, 1);
+    let requested = Region::new(100.into(), 30, 1);
+
+    assert!(machine.push(Alloc(existing)).is_ok());
+    assert_eq!(
+        Err(Violation::OversizedFree {
+            requested,
+            existing
+        }),
+        machine.push(Free(requested))
+    );
+    assert_eq!(
+        Err(Violation::OversizedFree {
+            requested,
+            existing
+        }),
+        machine.push(Realloc(Realloc::new(
+            None,
+            requested,
+            Region::new(200.into(), 10, 1)
+        )))
+    );
+
+    let mut machine = Machine::default().with_partial_frees(true);
+    assert!(machine.push(Alloc(existing)).is_ok());
+    assert_eq!(
+        Err(Violation::OversizedFree {
+            requested,
+            existing
+        }),
+        machine.push(Free(requested))
+    );
+
+    // Note: the region is left untouched.
+    assert_eq!(vec![existing], machine.trailing_regions());
+}
+
+#[test]
+fn test_max_align() {
+    let mut machine = Machine::default();
+    let requested = Region::new(0x1000.into(), 64, 4096);
+    assert!(machine.push(Alloc(requested)).is_ok());
+
+    let mut machine = Machine::default().with_max_align(16);
+    assert_eq!(
+        Err(Violation::ExcessiveAlign { requested, max: 16 }),
+        machine.push(Alloc(requested))
+    );
+    assert_eq!(
+        Err(Violation::ExcessiveAlign { requested, max: 16 }),
+        machine.push(Free(requested))
+    );
+}
+
+#[test]
+fn test_alloc_zeroed() {
+    let mut machine = Machine::default();
+    let requested = Region::new(100.into(), 10, 1);
+
+    assert_eq!(
+        Err(Violation::NonZeroedAlloc { requested }),
+        machine.push(AllocZeroed(checkers::AllocZeroed::new(
+            Some(false),
+            requested
+        )))
+    );
+
+    // Note: the region is still allocated.
+    assert_eq!(10, machine.memory_used());
+    assert!(machine.push(Free(requested)).is_ok());
+
+    let misaligned = Region::new(101.into(), 10, 2);
+    assert_eq!(
+        Err(Violation::MisalignedAlloc {
+            requested: misaligned
+        }),
+        machine.push(AllocZeroed(checkers::AllocZeroed::new(
+            Some(false),
+            misaligned
+        )))
+    );
+
+    assert!(machine
+        .push(AllocZeroed(checkers::AllocZeroed::new(
+            Some(true),
+            requested
+        )))
+        .is_ok());
+    assert_eq!(
+        Err(Violation::ConflictingAlloc {
+            requested,
+            existing: requested
+        }),
+        machine.push(AllocZeroed(checkers::AllocZeroed::new(None, requested)))
+    );
+}
+
+#[test]
+fn test_free_by_ptr() {
+    let mut machine = Machine::default();
+
+    let region = Region::new(100.into(), 20, 4);
+    assert!(machine.push(Alloc(region)).is_ok());
+    assert_eq!(20, machine.memory_used());
+
+    assert!(machine.push(FreeByPtr(100.into())).is_ok());
+    assert_eq!(0, machine.memory_used());
+    assert_eq!(None, machine.region_at(100.into()));
+
+    assert_eq!(
+        Err(Violation::MissingFree {
+            requested: Region::new(100.into(), 0, 1)
+        }),
+        machine.push(FreeByPtr(100.into()))
+    );
+
+    // Note: the pointer has to be the start of a region.
+    assert!(machine.push(Alloc(region)).is_ok());
+    assert!(machine.push(FreeByPtr(110.into())).is_err());
+    assert_eq!(20, machine.memory_used());
+}
+
+#[test]
+fn test_high_address_neighbors() {
+    let mut machine = Machine::default();
+
+    // Note: `top` ends exactly at `usize::MAX`, which doesn't overflow.
+    let below = Region::new((usize::MAX - 64).into(), 32, 1);
+    let top = Region::new((usize::MAX - 32).into(), 32, 1);
+
+    assert!(machine.push(Alloc(below)).is_ok());
+    assert!(machine.push(Alloc(top)).is_ok());
+    assert_eq!(
+        vec![below, top],
+        machine
+            .overlapping(below.coalesce(top).unwrap())
+            .collect::<Vec<_>>()
+    );
+
+    assert!(machine.push(Free(top)).is_ok());
+
+    let requested = Region::new((usize::MAX - 32).into(), 33, 1);
+    assert_eq!(
+        Err(Violation::RegionOverflow { requested }),
+        machine.push(Alloc(requested))
+    );
+    assert_eq!(0, machine.overlapping(requested).count());
+
+    assert!(machine.push(Alloc(top)).is_ok());
+    assert!(machine.push(Free(below)).is_ok());
+    assert!(machine.push(Free(top)).is_ok());
+    assert_eq!(0, machine.memory_used());
+}
+
+#[test]
+fn test_replay_lenient() {
+    let a = Region::new(100.into(), 10, 1);
+    let b = Region::new(105.into(), 10, 1);
+
+    let mut machine = Machine::default();
+
+    let violations = machine.replay_lenient(vec![
+        Alloc(a),
+        // Conflicts with `a`, so it can't be created.
+        Free(b),
+        FreeByPtr(200.into()),
+        Free(a),
+        Free(a),
+    ]);
+
+    assert_eq!(
+        vec![
+            (1, Violation::MissingFree { requested: b }),
+            (
+                2,
+                Violation::MissingFree {
+                    requested: Region::new(200.into(), 0, 1)
+                }
+            ),
+            (4, Violation::MissingFree { requested: a }),
+        ],
+        violations
+    );
+
+    assert!(machine.is_clean());
+    assert_eq!(10, machine.total_allocated());
+}
+
+#[test]
+fn test_lifo_free_order() {
+    let mut machine = Machine::default()
+        .with_free_order(FreeOrder::Lifo)
+        .with_partial_frees(true);
+
+    let a = Region::new(100.into(), 10, 1);
+    let b = Region::new(200.into(), 20, 1);
+    let c = Region::new(300.into(), 40, 1);
+
+    assert!(machine.push(Alloc(a)).is_ok());
+    assert!(machine.push(Alloc(b)).is_ok());
+
+    // Reallocating `b` keeps its position on top.
+    assert!(machine.push(Realloc(Realloc::new(None, b, c))).is_ok());
+
+    assert_eq!(
+        Err(Violation::OutOfOrderFree {
+            requested: a,
+            expected: c
+        }),
+        machine.push(Free(a))
+    );
+    assert_eq!(
+        Err(Violation::OutOfOrderFree {
+            requested: a,
+            expected: c
+        }),
+        machine.push(FreeByPtr(a.ptr))
+    );
+
+    // Freeing part of `c` leaves the rest of it on top.
+    assert!(machine.push(Free(Region::new(300.into(), 20, 1))).is_ok());
+    assert!(machine.push(Free(a)).is_err());
+    assert!(machine.push(Free(Region::new(320.into(), 20, 1))).is_ok());
+    assert!(machine.push(FreeByPtr(a.ptr)).is_ok());
+    assert!(machine.is_clean());
+}
+
+#[test]
+fn test_merge_offsets_history() {
+    let a = Region::new(100.into(), 10, 1);
+    let b = Region::new(200.into(), 10, 1);
+    let c = Region::new(300.into(), 10, 1);
+
+    let mut first = Machine::default()
+        .with_provenance(true)
+        .with_free_order(FreeOrder::Lifo);
+    assert!(first.push(Alloc(a)).is_ok());
+
+    let mut second = Machine::default().with_provenance(true).with_tombstones(4);
+    assert!(second.push(Alloc(c)).is_ok());
+    assert!(second.push(Free(c)).is_ok());
+    assert!(second.push(Alloc(b)).is_ok());
+
+    assert!(first.merge(second).is_ok());
+    assert_eq!(4, first.events_processed());
+    assert_eq!(Some(0), first.established_at(a.ptr));
+    assert_eq!(Some(3), first.established_at(b.ptr));
+
+    // The tombstone of `c` was kept, with its index offset.
+    assert_eq!(
+        Err(Violation::QuickReuse {
+            requested: c,
+            freed_at: 2
+        }),
+        first.push(Alloc(c))
+    );
+
+    // Regions of the merged machine count as more recently allocated.
+    assert!(first.push(Free(c)).is_ok());
+    assert_eq!(
+        Err(Violation::OutOfOrderFree {
+            requested: a,
+            expected: b
+        }),
+        first.push(Free(a))
+    );
+    assert!(first.push(Free(b)).is_ok());
+    assert!(first.push(Free(a)).is_ok());
+}
+
+#[test]
+fn test_adopted_frees() {
+    let mut machine = Machine::default().with_adopted_frees(true);
+    let live = Region::new(100.into(), 20, 1);
+    assert!(machine.push(Alloc(live)).is_ok());
+
+    // Frees inside of a live region are never adopted.
+    let inner = Region::new(110.into(), 10, 1);
+    assert_eq!(
+        Err(Violation::MissingFree { requested: inner }),
+        machine.push(Free(inner))
+    );
+    assert!(machine.push(FreeByPtr(110.into())).is_err());
+
+    assert!(machine.push(FreeByPtr(400.into())).is_ok());
+    assert_eq!(
+        &[Region::new(400.into(), 0, 1)][..],
+        machine.adopted_frees()
+    );
+
+    assert!(machine.push(Free(live)).is_ok());
+    assert!(machine.push(Free(live)).is_err());
+    assert!(machine.is_clean());
+}
+
+#[test]
+fn test_adopted_frees_disabled() {
+    let mut machine = Machine::default();
+    let region = Region::new(100.into(), 20, 1);
+    assert_eq!(
+        Err(Violation::MissingFree { requested: region }),
+        machine.push(Free(region))
+    );
+    assert!(machine.adopted_frees().is_empty());
+}
+
+#[test]
+fn test_page_size() {
+    let mut machine = Machine::default().with_page_size(0x1000);
+    let page_size = 0x1000;
+
+    // Ends exactly at a boundary.
+    assert!(machine
+        .push(Alloc(Region::new(0x1f00.into(), 0x100, 1)))
+        .is_ok());
+    // Larger than a page.
+    assert!(machine
+        .push(Alloc(Region::new(0x4800.into(), 0x1001, 1)))
+        .is_ok());
+
+    let requested = Region::new(0x2800.into(), 0x1000, 1);
+    assert_eq!(
+        Err(Violation::CrossesPageBoundary {
+            requested,
+            page_size
+        }),
+        machine.push(Alloc(requested))
+    );
+
+    let free = Region::new(0x8000.into(), 0x10, 1);
+    let alloc = Region::new(0x8ff8.into(), 0x10, 1);
+    assert!(machine.push(Alloc(free)).is_ok());
+    assert_eq!(
+        Err(Violation::CrossesPageBoundary {
+            requested: alloc,
+            page_size
+        }),
+        machine.push(Realloc(Realloc::new(None, free, alloc)))
+    );
+
+    // Violating allocations are still recorded.
+    assert!(machine.push(Free(requested)).is_ok());
+    assert!(machine.push(Free(alloc)).is_ok());
+}
+
+#[test]
+fn test_page_size_non_zeroed() {
+    let mut machine = Machine::default().with_page_size(0x1000);
+
+    let requested = Region::new(0x1f00.into(), 0x200, 1);
+    assert_eq!(
+        Err(Violation::NonZeroedAlloc { requested }),
+        machine.push(AllocZeroed(AllocZeroed::new(Some(false), requested)))
+    );
+
+    let requested = Region::new(0x3f00.into(), 0x200, 1);
+    assert_eq!(
+        Err(Violation::CrossesPageBoundary {
+            requested,
+            page_size: 0x1000
+        }),
+        machine.push(AllocZeroed(AllocZeroed::new(Some(true), requested)))
+    );
+}
+
+#[test]
+#[should_panic = "page size must be a power of two"]
+#[cfg(debug_assertions)]
+fn test_page_size_not_power_of_two() {
+    let _ = Machine::default().with_page_size(0x1800);
+}
+
+#[test]
+fn test_realloc_to_zero() {
+    let mut machine = Machine::default();
+    let a = Region::new(100.into(), 10, 1);
+    let b = Region::new(200.into(), 10, 1);
+
+    assert!(machine.push(Alloc(a)).is_ok());
+    assert!(machine.push(Alloc(b)).is_ok());
+
+    // Relocating to a zero-sized region doesn't leave it behind.
+    let realloc = Realloc::new(None, a, Region::new(300.into(), 0, 1));
+    assert!(machine.push(Realloc(realloc)).is_ok());
+    assert_eq!(vec![b], machine.trailing_regions());
+    assert_eq!(10, machine.memory_used());
+
+    // The region is gone, so this is like a double free.
+    assert_eq!(
+        Err(Violation::MissingFree { requested: a }),
+        machine.push(Realloc(realloc))
+    );
+
+    assert!(machine.push(Free(b)).is_ok());
+    assert_eq!(&[a][..], machine.zero_reallocs());
+
+    let mut machine = Machine::default().with_free_order(FreeOrder::Lifo);
+    assert!(machine.push(Alloc(a)).is_ok());
+    assert!(machine.push(Alloc(b)).is_ok());
+    assert_eq!(
+        Err(Violation::OutOfOrderFree {
+            requested: a,
+            expected: b
+        }),
+        machine.push(Realloc(realloc))
+    );
+
+    let mut machine = Machine::default()
+        .with_adopted_frees(true)
+        .with_strict_zero_reallocs(true);
+    assert!(machine.push(Realloc(realloc)).is_ok());
+    assert_eq!(&[a][..], machine.adopted_frees());
+    assert!(machine.zero_reallocs().is_empty());
+    assert!(machine.is_clean());
+}
+
+#[test]
+fn test_lenient_free_align() {
+    let existing = Region::new(0x100.into(), 16, 8);
+    let requested = Region::new(0x100.into(), 16, 1);
+
+    let mut machine = Machine::default();
+    assert!(machine.push(Alloc(existing)).is_ok());
+    assert_eq!(
+        Err(Violation::MisalignedFree {
+            requested,
+            existing
+        }),
+        machine.push(Free(requested))
+    );
+
+    let mut machine = Machine::default().with_lenient_free_align(true);
+    assert!(machine.push(Alloc(existing)).is_ok());
+
+    // The size still needs to match.
+    let oversized = Region::new(0x100.into(), 32, 1);
+    assert_eq!(
+        Err(Violation::OversizedFree {
+            requested: oversized,
+            existing
+        }),
+        machine.push(Free(oversized))
+    );
+
+    let alloc = Region::new(0x200.into(), 32, 8);
+    assert!(machine
+        .push(Realloc(Realloc::new(None, requested, alloc)))
+        .is_ok());
+    assert_eq!(vec![alloc], machine.trailing_regions());
+}
@@ -0,0 +1,48 @@
+#[global_allocator]
+static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+
+#[test]
+fn test_nested_scopes() {
+    let mut snapshots = Vec::new();
+
+    let outer = checkers::with(|| {
+        for n in 1..=2 {
+            let snapshot = checkers::with(|| {
+                let _ = vec![0u8; n * 8];
+            });
+
+            checkers::with_muted(|| snapshots.push(snapshot));
+        }
+    });
+
+    assert_eq!(2, snapshots.len());
+
+    for (n, snapshot) in snapshots.iter().enumerate() {
+        assert_eq!(1, snapshot.events.allocs());
+        assert_eq!(1, snapshot.events.frees());
+        assert_eq!((n + 1) * 8, snapshot.peak_memory);
+    }
+
+    assert_eq!(2, outer.events.allocs());
+    assert_eq!(2, outer.events.frees());
+    assert_eq!(16, outer.peak_memory);
+}
+
+#[test]
+fn test_panicking_nested_scope() {
+    let result = std::panic::catch_unwind(|| {
+        checkers::with(|| {
+            checkers::with(|| panic!("uh oh"));
+        })
+    });
+
+    assert!(result.is_err());
+
+    // Note: state is cleared for the next scope, since the panicking scopes
+    // were left.
+    let snapshot = checkers::with(|| {
+        let _ = vec![0u8; 8];
+    });
+
+    assert_eq!(2, snapshot.events.len());
+}
@@ -0,0 +1,34 @@
+use checkers::{Allocator, FailingAllocator};
+use std::alloc::{GlobalAlloc, Layout, System};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<FailingAllocator> = Allocator::new(FailingAllocator::new(System, 0));
+
+#[test]
+fn test_oom_after() {
+    let layout = Layout::from_size_align(10, 1).unwrap();
+
+    let snapshot = checkers::with(|| unsafe {
+        ALLOCATOR.delegate().reset(2);
+
+        let a = ALLOCATOR.alloc(layout);
+        assert!(!a.is_null());
+        let b = ALLOCATOR.realloc(a, layout, 20);
+        assert!(!b.is_null());
+        assert!(ALLOCATOR.alloc_zeroed(layout).is_null());
+        assert!(ALLOCATOR.realloc(b, layout, 30).is_null());
+
+        ALLOCATOR.dealloc(b, Layout::from_size_align(20, 1).unwrap());
+    });
+
+    assert_eq!(5, snapshot.events.len());
+    assert!(snapshot.events[0].is_alloc_with(|r| r.size == 10));
+    assert!(snapshot.events[1].is_realloc_with(|r| r.alloc.size == 20));
+    assert_eq!(checkers::Event::AllocZeroedFailed, snapshot.events[2]);
+    assert_eq!(checkers::Event::ReallocFailed, snapshot.events[3]);
+    assert!(snapshot.events[4].is_free_with(|r| r.size == 20));
+
+    let mut violations = Vec::new();
+    snapshot.validate(&mut violations);
+    assert!(violations.is_empty());
+}
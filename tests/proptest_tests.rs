@@ -0,0 +1,27 @@
+#![cfg(feature = "proptest")]
+
+use checkers::{Event, Machine};
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn generated_histories_are_valid(events in checkers::event_strategy(32, 4096)) {
+        let mut machine = Machine::default();
+        prop_assert!(machine.push_all(events.iter().copied()).is_empty());
+        prop_assert_eq!(0, machine.memory_used());
+        prop_assert!(events.len() <= 64);
+    }
+
+    #[test]
+    fn dropped_free_is_a_leak(events in checkers::event_strategy(8, 64)) {
+        let mut events = events;
+
+        if let Some(index) = events.iter().position(|e| matches!(e, Event::Free(..))) {
+            events.remove(index);
+
+            let mut machine = Machine::default();
+            prop_assert!(machine.push_all(events).is_empty());
+            prop_assert_eq!(1, machine.leaks().count());
+        }
+    }
+}
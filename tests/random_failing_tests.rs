@@ -0,0 +1,60 @@
+use checkers::{Allocator, Event, RandomFailingAllocator};
+use std::alloc::{GlobalAlloc, Layout, System};
+
+#[global_allocator]
+static ALLOCATOR: Allocator<RandomFailingAllocator> =
+    Allocator::new(RandomFailingAllocator::new(System, 1, 0.25));
+
+fn pattern(seed: u64) -> (Vec<bool>, checkers::Snapshot) {
+    let layout = Layout::from_size_align(32, 8).unwrap();
+    // Note: allocating within the snapshot could fail.
+    let mut results = Vec::with_capacity(64);
+
+    let snapshot = checkers::with(|| unsafe {
+        ALLOCATOR.delegate().reset(seed);
+
+        for _ in 0..64 {
+            let ptr = ALLOCATOR.alloc(layout);
+            results.push(ptr.is_null());
+
+            if !ptr.is_null() {
+                ALLOCATOR.dealloc(ptr, layout);
+            }
+        }
+    });
+
+    (results, snapshot)
+}
+
+#[test]
+fn test_random_failures_are_reproducible() {
+    let (a, snapshot) = pattern(1234);
+    let (b, _) = pattern(1234);
+    let (c, _) = pattern(4321);
+
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+
+    let failed = a.iter().filter(|f| **f).count();
+    assert!(failed > 0 && failed < 64);
+
+    let injected = snapshot
+        .events
+        .iter()
+        .filter(|e| matches!(e, Event::InjectedFailure(r) if r.size == 32 && r.align == 8))
+        .count();
+
+    assert_eq!(failed, injected);
+    assert_eq!(
+        failed,
+        snapshot
+            .events
+            .iter()
+            .filter(|e| **e == Event::AllocFailed)
+            .count()
+    );
+
+    let mut violations = Vec::new();
+    snapshot.validate(&mut violations);
+    assert!(violations.is_empty());
+}
@@ -34,6 +34,7 @@ fn test_realloc() {
         let mut v = Vec::<u32>::new();
         v.reserve_exact(1);
         v.push(1);
+        v.reserve_exact(1);
         v.push(2);
     });
 
@@ -0,0 +1,27 @@
+#![cfg(feature = "serde")]
+
+use checkers::{Event::*, Machine, Region};
+
+#[test]
+fn test_serde_roundtrip() {
+    let a = Region::new(0x10.into(), 0x10, 1);
+    let b = Region::new(0x18.into(), 0x10, 1);
+    let events = vec![Alloc(a), Alloc(b), Free(a), Free(a)];
+
+    let json = serde_json::to_string(&events).unwrap();
+    assert!(json.contains(r#""ptr":16"#));
+
+    let replayed = serde_json::from_str::<Vec<checkers::Event>>(&json).unwrap();
+    assert_eq!(events, replayed);
+
+    let expected = Machine::default().push_all(events);
+    let violations = Machine::default().push_all(replayed);
+    assert_eq!(2, violations.len());
+    assert_eq!(expected, violations);
+
+    let json = serde_json::to_string(&violations).unwrap();
+    assert_eq!(
+        violations,
+        serde_json::from_str::<Vec<(usize, checkers::Violation)>>(&json).unwrap()
+    );
+}
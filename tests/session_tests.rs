@@ -0,0 +1,49 @@
+#[global_allocator]
+static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+
+#[test]
+fn test_session_nests_with_scopes() {
+    let outer = checkers::begin();
+    let a = vec![1u8; 16];
+
+    let inner = checkers::with(|| {
+        let _ = vec![1u8; 32];
+    });
+
+    drop(a);
+    let outer = outer.finish();
+
+    assert_eq!(2, inner.events.len());
+    assert_eq!(32, inner.peak_memory);
+    assert_eq!(4, outer.events.len());
+    assert_eq!(48, outer.peak_memory);
+}
+
+#[test]
+fn test_session_stops_capturing_on_drop() {
+    let outer = checkers::begin();
+
+    {
+        let _inner = checkers::begin();
+        let _ = Box::new(1);
+    }
+
+    let _ = Box::new(2);
+    assert_eq!(4, outer.finish().events.len());
+
+    let snapshot = checkers::with(|| ());
+    assert!(snapshot.events.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "1 region leaked")]
+fn test_session_panics_on_leak() {
+    let _session = checkers::begin().with_drop_policy(checkers::DropPolicy::PanicOnLeak);
+    std::mem::forget(vec![1u8; 16]);
+}
+
+#[test]
+fn test_session_silent_on_leak() {
+    let _session = checkers::begin();
+    std::mem::forget(vec![1u8; 16]);
+}
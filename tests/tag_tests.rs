@@ -0,0 +1,46 @@
+use checkers::Machine;
+
+#[global_allocator]
+static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+
+#[test]
+fn test_tag_restored_after_panic() {
+    let result = std::panic::catch_unwind(|| {
+        checkers::with_tag("outer", || {
+            checkers::with_tag("inner", || panic!("uh oh"));
+        })
+    });
+
+    assert!(result.is_err());
+
+    let snapshot = checkers::with(|| {
+        let _ = Box::new(1);
+    });
+
+    assert_eq!(None, snapshot.events.tag(0));
+    assert!(snapshot.group_by_tag().is_empty());
+}
+
+#[test]
+fn test_tagged_leak() {
+    let mut leaked = None;
+
+    let snapshot = checkers::with(|| {
+        checkers::with_tag("parser", || {
+            leaked = Some(Box::into_raw(vec![0u8; 64].into_boxed_slice()));
+        });
+
+        let _ = Box::new(1);
+    });
+
+    // Tags are ignored when validating.
+    let mut machine = Machine::default();
+    assert!(machine.push_all(snapshot.events.iter().copied()).is_empty());
+    assert_eq!(64, machine.memory_used());
+
+    let parser = snapshot.group_by_tag().remove("parser").unwrap();
+    assert_eq!(1, parser.len());
+    assert!(parser[0].1.is_alloc_with(|r| r.size == 64));
+
+    drop(unsafe { Box::from_raw(leaked.unwrap()) });
+}
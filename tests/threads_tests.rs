@@ -0,0 +1,212 @@
+use std::sync::{mpsc, Arc, Barrier, Mutex};
+use std::thread;
+
+use checkers::Machine;
+
+#[global_allocator]
+static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+
+// Note: calls to `with_threads` can't overlap, and tests run in parallel.
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[test]
+fn test_with_threads_attributes_events() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let mut ids = Vec::new();
+
+    let snapshot = checkers::with_threads(|| {
+        let a = thread::spawn(|| {
+            let _ = vec![1u8; 100];
+            thread::current().id()
+        });
+
+        let b = thread::spawn(|| {
+            let _ = vec![1u8; 200];
+            thread::current().id()
+        });
+
+        ids.push(a.join().unwrap());
+        ids.push(b.join().unwrap());
+    });
+
+    let a = snapshot
+        .events
+        .by_thread(ids[0])
+        .map(|(_, e)| e)
+        .collect::<Vec<_>>();
+    let b = snapshot
+        .events
+        .by_thread(ids[1])
+        .map(|(_, e)| e)
+        .collect::<Vec<_>>();
+
+    assert!(a.iter().any(|e| e.is_alloc_with(|r| r.size == 100)));
+    assert!(a.iter().any(|e| e.is_free_with(|r| r.size == 100)));
+    assert!(!a.iter().any(|e| e.is_alloc_with(|r| r.size == 200)));
+    assert!(b.iter().any(|e| e.is_alloc_with(|r| r.size == 200)));
+    assert!(!b.iter().any(|e| e.is_alloc_with(|r| r.size == 100)));
+}
+
+#[test]
+fn test_with_threads_merges_in_order() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let snapshot = checkers::with_threads(|| {
+        let (tx, rx) = mpsc::channel();
+
+        let producer = thread::spawn(move || {
+            for _ in 0..16 {
+                tx.send(vec![1u8; 1234]).unwrap();
+            }
+        });
+
+        let consumer = thread::spawn(move || {
+            for buffer in rx {
+                drop(buffer);
+            }
+        });
+
+        producer.join().unwrap();
+        consumer.join().unwrap();
+    });
+
+    let mut machine = Machine::default();
+    let mut allocs = 0;
+
+    for event in snapshot.events.iter() {
+        if event.is_alloc_with(|r| r.size == 1234) {
+            allocs += 1;
+        } else if !event.is_free_with(|r| r.size == 1234) {
+            continue;
+        }
+
+        // Note: frees happen on another thread, but must still be ordered
+        // after the allocation they free.
+        assert_eq!(Ok(()), machine.push(*event));
+    }
+
+    assert_eq!(16, allocs);
+    assert!(machine.trailing_regions().is_empty());
+    assert!(snapshot.peak_memory >= 1234);
+}
+
+#[test]
+fn test_with_threads_many_threads() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let snapshot = checkers::with_threads(|| {
+        let handles = (0..8)
+            .map(|_| {
+                thread::spawn(|| {
+                    for _ in 0..100 {
+                        let _ = vec![1u8; 4321];
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    });
+
+    let allocs = snapshot
+        .events
+        .iter()
+        .filter(|e| e.is_alloc_with(|r| r.size == 4321))
+        .count();
+
+    assert_eq!(800, allocs);
+    assert!(snapshot.events.len() >= 1600);
+}
+
+#[test]
+fn test_with_threads_replays_whole_history() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+
+    for _ in 0..20 {
+        let snapshot = checkers::with_threads(|| {
+            let (tx, rx) = mpsc::channel::<Vec<u8>>();
+
+            let consumer = thread::spawn(move || {
+                for mut buffer in rx {
+                    buffer.extend_from_slice(&[2u8; 64]);
+                    drop(buffer);
+                }
+            });
+
+            let producers = (0..4)
+                .map(|n| {
+                    let tx = tx.clone();
+
+                    thread::spawn(move || {
+                        for size in 1..200 {
+                            let mut buffer = Vec::new();
+
+                            // Note: pushed one at a time so that the
+                            // buffer is reallocated as it grows.
+                            for byte in 0..size % 17 + n {
+                                buffer.push(byte as u8);
+                            }
+
+                            tx.send(buffer).unwrap();
+                            let _ = vec![0u8; size];
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            drop(tx);
+
+            for producer in producers {
+                producer.join().unwrap();
+            }
+
+            consumer.join().unwrap();
+        });
+
+        let mut machine = Machine::default();
+
+        // Note: a region must never be reallocated by one thread before the
+        // thread which held it has recorded freeing it.
+        for event in snapshot.events.iter() {
+            assert_eq!(Ok(()), machine.push(*event), "{:?}", event);
+        }
+    }
+}
+
+#[test]
+fn test_with_threads_alongside_with() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let barrier = Arc::new(Barrier::new(2));
+
+    let local = {
+        let barrier = barrier.clone();
+
+        thread::spawn(move || {
+            checkers::with(|| {
+                barrier.wait();
+                let _ = vec![1u8; 777];
+                barrier.wait();
+            })
+        })
+    };
+
+    let snapshot = checkers::with_threads(|| {
+        barrier.wait();
+        barrier.wait();
+    });
+
+    let local = local.join().unwrap();
+
+    assert!(local
+        .events
+        .iter()
+        .any(|e| e.is_alloc_with(|r| r.size == 777)));
+    assert!(local
+        .events
+        .iter()
+        .any(|e| e.is_free_with(|r| r.size == 777)));
+    assert!(!snapshot
+        .events
+        .iter()
+        .any(|e| e.is_alloc_with(|r| r.size == 777)));
+}
@@ -0,0 +1,33 @@
+#![cfg(feature = "timestamps")]
+
+use std::time::Duration;
+
+#[global_allocator]
+static ALLOCATOR: checkers::Allocator = checkers::Allocator::system();
+
+#[test]
+fn test_timestamps() {
+    let mut inner = None;
+
+    let snapshot = checkers::with(|| {
+        let _ = Box::new(1);
+        std::thread::sleep(Duration::from_millis(20));
+
+        inner = Some(checkers::with(|| {
+            let _ = Box::new(2);
+        }));
+    });
+
+    let inner = inner.unwrap();
+
+    let first = snapshot.events.timestamp(0).unwrap();
+    let last = snapshot.events.timestamp(3).unwrap();
+    assert!(last >= first + Duration::from_millis(20));
+
+    // Note: timestamps of nested snapshots are relative to their own start.
+    assert!(inner.events.timestamp(0).unwrap() < Duration::from_millis(20));
+
+    let rate = snapshot.events.allocs_over_time(Duration::from_millis(20));
+    assert_eq!(1, rate[0]);
+    assert_eq!(2, rate.iter().sum::<usize>());
+}
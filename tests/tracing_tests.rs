@@ -0,0 +1,49 @@
+#![cfg(feature = "tracing")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tracing::{span, Event, Metadata, Subscriber};
+
+#[global_allocator]
+static ALLOCATOR: checkers::Allocator = checkers::Allocator::system().with_tracing(true);
+
+static EVENTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Subscriber counting events emitted by the allocator.
+struct Counter;
+
+impl Subscriber for Counter {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target() == "checkers::alloc"
+    }
+
+    fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        // Note: allocating while handling the event must not recurse.
+        let _ = format!("{:?}", event);
+        EVENTS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn enter(&self, _: &span::Id) {}
+
+    fn exit(&self, _: &span::Id) {}
+}
+
+#[test]
+fn test_tracing_events() {
+    let snapshot = tracing::subscriber::with_default(Counter, || {
+        checkers::with(|| {
+            let mut v = Vec::<u8>::with_capacity(16);
+            v.reserve_exact(32);
+        })
+    });
+
+    assert_eq!(3, snapshot.events.len());
+    assert_eq!(3, EVENTS.load(Ordering::SeqCst));
+}